@@ -0,0 +1,26 @@
+//! Times move generation by counting every position a few moves deep with perft.
+//! Run with `cargo bench`, it only needs the standard library
+
+use std::time::Instant;
+
+use avidf_chess::Game;
+
+fn main() {
+    for (name, fen, depth) in [
+        ("start", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 5),
+        ("kiwipete", "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 4),
+    ] {
+        let game = Game::from_fen(fen).unwrap();
+        let start = Instant::now();
+        let nodes = game.perft(depth);
+        let elapsed = start.elapsed();
+        println!(
+            "{:<10} depth {} {:>9} nodes {:>8.1} ms {:>10.0} nodes/s",
+            name,
+            depth,
+            nodes,
+            elapsed.as_secs_f64() * 1000.0,
+            nodes as f64 / elapsed.as_secs_f64()
+        );
+    }
+}
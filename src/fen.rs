@@ -0,0 +1,204 @@
+use crate::lib_of_lib::bitboard::Board;
+use crate::lib_of_lib::castling::CastlingRights;
+use crate::{ChessError, Color, File, Game, Piece, Rank, Square};
+
+/// Reading and writing positions in Forsyth-Edwards Notation, for example
+/// "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+impl Game {
+    /// Sets up the position described by the FEN text.
+    /// The two move counters at the end may be left out, they then start at 0 and 1
+    pub fn from_fen(fen: &str) -> Result<Game, ChessError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 4 && fields.len() != 6 {
+            return Err(invalid(format!("expected 4 or 6 fields, found {}", fields.len())));
+        }
+
+        let mut game = Game::new();
+        game.board = Board::empty();
+        read_placement(&mut game, fields[0])?;
+
+        game.color = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(invalid(format!("\"{}\" is not a side to move, expected w or b", other))),
+        };
+        game.castling = read_castling(&game, fields[2])?;
+        game.en_passant = match fields[3] {
+            "-" => None,
+            text => {
+                let square: Square = text
+                    .parse()
+                    .map_err(|_| invalid(format!("\"{}\" is not an en passant square", text)))?;
+                let expected = match game.color {
+                    Color::White => Rank::Sixth,
+                    Color::Black => Rank::Third,
+                };
+                if square.rank() != expected {
+                    return Err(invalid(format!("{} cannot be an en passant square with this side to move", square)));
+                }
+                let moved = game.color.opposite();
+                let forward = game.forward(moved);
+                let pushed = square.offset(forward, 0).filter(|&pawn| game.piece_at(pawn) == Some((Piece::Pawn, moved)));
+                let origin = square.offset(-forward, 0).filter(|&origin| game.piece_at(origin).is_none());
+                if pushed.is_none() || origin.is_none() || game.piece_at(square).is_some() {
+                    return Err(invalid(format!("no pawn can just have passed over {}", square)));
+                }
+                Some(square)
+            }
+        };
+        if fields.len() == 6 {
+            game.halfmove_clock = fields[4]
+                .parse()
+                .map_err(|_| invalid(format!("\"{}\" is not a halfmove clock", fields[4])))?;
+            game.fullmove_number = match fields[5].parse() {
+                Ok(number) if number > 0 => number,
+                _ => return Err(invalid(format!("\"{}\" is not a fullmove number", fields[5]))),
+            };
+        } else {
+            game.halfmove_clock = 0;
+            game.fullmove_number = 1;
+        }
+
+        for color in [Color::White, Color::Black] {
+            let kings = Square::all()
+                .filter(|&square| game.piece_at(square) == Some((Piece::King, color)))
+                .count();
+            if kings != 1 {
+                return Err(invalid(format!("{:?} has {} kings", color, kings)));
+            }
+        }
+        if game.in_check(game.color.opposite()) {
+            return Err(invalid("the side not to move is in check".to_string()));
+        }
+        game.hash = game.full_hash();
+        game.reset_repetitions();
+        game.gamestate = game.check_checker();
+        Ok(game)
+    }
+
+    /// The current position as FEN text
+    pub fn to_fen(&self) -> String {
+        let mut ranks: Vec<String> = Vec::with_capacity(8);
+        for rank in Rank::ALL.iter().rev() {
+            let mut row = String::new();
+            let mut empty = 0;
+            for file in File::ALL {
+                match self.piece_at(Square::new(file, *rank)) {
+                    None => empty += 1,
+                    Some((piece, color)) => {
+                        if empty > 0 {
+                            row.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        row.push(piece_char(piece, color));
+                    }
+                }
+            }
+            if empty > 0 {
+                row.push_str(&empty.to_string());
+            }
+            ranks.push(row);
+        }
+
+        let side = match self.color {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+        let en_passant = self
+            .en_passant
+            .map(|square| square.to_string())
+            .unwrap_or_else(|| "-".to_string());
+
+        format!(
+            "{} {} {} {} {} {}",
+            ranks.join("/"),
+            side,
+            self.castling,
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+}
+
+fn invalid(reason: String) -> ChessError {
+    ChessError::InvalidFen(reason)
+}
+
+/// Uppercase letters for White, lowercase for Black
+pub(crate) fn piece_char(piece: Piece, color: Color) -> char {
+    match color {
+        Color::White => piece.to_char().to_ascii_uppercase(),
+        Color::Black => piece.to_char(),
+    }
+}
+
+/// The pieces from the eighth rank down to the first,
+/// with digits counting empty squares
+fn read_placement(game: &mut Game, placement: &str) -> Result<(), ChessError> {
+    let rows: Vec<&str> = placement.split('/').collect();
+    if rows.len() != 8 {
+        return Err(invalid(format!("expected 8 ranks, found {}", rows.len())));
+    }
+    for (row, rank) in rows.iter().zip(Rank::ALL.iter().rev()) {
+        let mut file = 0;
+        let mut after_digit = false;
+        for c in row.chars() {
+            if let Some(empty) = c.to_digit(10).filter(|empty| (1..=8).contains(empty)) {
+                if after_digit {
+                    return Err(invalid(format!("rank {} has two digits in a row", rank)));
+                }
+                after_digit = true;
+                file += empty as usize;
+            } else {
+                after_digit = false;
+                let piece = Piece::from_char(c)
+                    .ok_or_else(|| invalid(format!("'{}' is not a piece", c)))?;
+                let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+                let square = File::from_index(file)
+                    .map(|file| Square::new(file, *rank))
+                    .ok_or_else(|| invalid(format!("rank {} has more than 8 squares", rank)))?;
+                if piece == Piece::Pawn && (*rank == Rank::First || *rank == Rank::Eighth) {
+                    return Err(invalid(format!("pawn on {}", square)));
+                }
+                game.set_piece(square, Some((piece, color)));
+                file += 1;
+            }
+        }
+        if file != 8 {
+            return Err(invalid(format!("rank {} has {} squares instead of 8", rank, file)));
+        }
+    }
+    Ok(())
+}
+
+/// The castling rights, each of which needs its king and rook
+/// still standing on the squares they started on
+fn read_castling(game: &Game, text: &str) -> Result<CastlingRights, ChessError> {
+    let mut castling = CastlingRights::none();
+    if text == "-" {
+        return Ok(castling);
+    }
+    for c in text.chars() {
+        let (right, color, corner) = match c {
+            'K' => (&mut castling.white_king_side, Color::White, File::H),
+            'Q' => (&mut castling.white_queen_side, Color::White, File::A),
+            'k' => (&mut castling.black_king_side, Color::Black, File::H),
+            'q' => (&mut castling.black_queen_side, Color::Black, File::A),
+            _ => return Err(invalid(format!("\"{}\" is not a castling field", text))),
+        };
+        if *right {
+            return Err(invalid(format!("\"{}\" repeats a castling right", text)));
+        }
+        let rank = match color {
+            Color::White => Rank::First,
+            Color::Black => Rank::Eighth,
+        };
+        let (king, rook) = (Square::new(File::E, rank), Square::new(corner, rank));
+        if game.piece_at(king) != Some((Piece::King, color)) || game.piece_at(rook) != Some((Piece::Rook, color)) {
+            return Err(invalid(format!("castling right {} needs a king on {} and a rook on {}", c, king, rook)));
+        }
+        *right = true;
+    }
+    Ok(castling)
+}
@@ -1,6 +1,64 @@
+use crate::lib_of_lib::color::Color;
+
+/// Where the game stands after a move
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum GameState {
     InProgress,
     Check,
-    GameOver,
-}
\ No newline at end of file
+    GameOver(Outcome),
+}
+
+/// Who won the game and why, or that it is not decided yet
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Ongoing,
+    WhiteWins(Reason),
+    BlackWins(Reason),
+    Draw(Reason),
+}
+
+/// The reason a game came to an end
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Reason {
+    Checkmate,
+    Stalemate,
+    Resignation,
+    Timeout,
+    Agreement,
+    FiftyMoveRule,
+    Repetition,
+    InsufficientMaterial,
+    /// A game record gives the result without saying how it came about
+    Unknown,
+}
+
+impl Outcome {
+    /// The given color has won the game
+    pub fn win(winner: Color, reason: Reason) -> Self {
+        match winner {
+            Color::White => Outcome::WhiteWins(reason),
+            Color::Black => Outcome::BlackWins(reason),
+        }
+    }
+
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            Outcome::WhiteWins(_) => Some(Color::White),
+            Outcome::BlackWins(_) => Some(Color::Black),
+            _ => None,
+        }
+    }
+
+    pub fn reason(&self) -> Option<Reason> {
+        match self {
+            Outcome::Ongoing => None,
+            Outcome::WhiteWins(reason) | Outcome::BlackWins(reason) | Outcome::Draw(reason) => {
+                Some(*reason)
+            }
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        *self != Outcome::Ongoing
+    }
+}
@@ -1,20 +1,80 @@
+use std::collections::HashMap;
 use std::fmt;
-use std::collections::HashSet;
 
 mod lib_of_lib;
 mod gamestate;
+mod fen;
+mod perft;
+mod san;
+mod uci;
+mod pgn;
+mod render;
+mod svg;
 
-use lib_of_lib::piece::Piece;
-use lib_of_lib::color::Color;
-use gamestate::GameState; 
+pub use lib_of_lib::piece::Piece;
+pub use lib_of_lib::color::Color;
+use lib_of_lib::bitboard::{self, Board};
+use lib_of_lib::castling::CastlingRights;
+use lib_of_lib::zobrist::KEYS;
+pub use lib_of_lib::square::{File, Rank, Square};
+pub use lib_of_lib::error::ChessError;
+pub use lib_of_lib::chess_move::{Move, MoveFlags};
+pub use gamestate::{GameState, Outcome, Reason};
+pub use pgn::{PgnGame, PgnMove, PgnReader, PgnWriter, SEVEN_TAG_ROSTER};
+pub use render::RenderOptions;
+pub use svg::SvgOptions;
 
+/// Halfmoves without a capture or pawn move after which a draw can be claimed
+const FIFTY_MOVES: u32 = 100;
+/// Halfmoves without a capture or pawn move after which the game is drawn
+const SEVENTY_FIVE_MOVES: u32 = 150;
+/// Times a position has to appear before a draw can be claimed
+const THREEFOLD: u32 = 3;
+/// Times a position has to appear before the game is drawn
+const FIVEFOLD: u32 = 5;
 
-#[derive(Copy, Clone, PartialEq)]
+/// What a pawn can become on the last rank
+const PROMOTIONS: [Piece; 4] = [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
+
+#[derive(Clone, PartialEq)]
 pub struct Game {
-    board: [[Option<(Piece, Color)>; 8]; 8],
-    promotion: Piece,
+    /// The pieces as bitboards, with the squares numbered the same way as `Square`,
+    /// from a1 = 0 to h8 = 63, so a1 is in the lower left corner as seen from White.
+    /// This is the only coordinate system of the engine and it never turns around,
+    /// showing the board from Black's side is left to the printing
+    board: Board,
     color: Color,
+    castling: CastlingRights,
+    /// The square a pawn skipped over with a double push on the last move,
+    /// where it can be taken en passant
+    en_passant: Option<Square>,
+    /// Moves since the last capture or pawn move, counted for each side
+    halfmove_clock: u32,
+    /// Starts at 1 and goes up after every move by Black
+    fullmove_number: u32,
+    gamestate: GameState,
+    /// Every move played so far with what it destroyed, the last move on top
+    history: Vec<Undo>,
+    /// Moves taken back with `undo_move`, the next one to redo on top
+    redo: Vec<Move>,
+    /// Zobrist hash of the position, kept up to date by every change to it
+    hash: u64,
+    /// How many times each position of the game has been on the board, by hash
+    repetitions: HashMap<u64, u32>,
+}
+
+/// A played move together with the state it destroyed,
+/// which is everything needed to take it back
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Undo {
+    chess_move: Move,
+    captured: Option<(Piece, Color)>,
+    castling: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
     gamestate: GameState,
+    hash: u64,
 }
 
 
@@ -28,67 +88,36 @@ pub struct Game {
 /// a situation in which I was stuck with a singular approach which dose not function 
 /// properly and is very strict
 /// The engine although logicaly sound dosent function properly
-
-
-
 impl Game {
     /// Initialises a new board with pieces.
     /// begining with white pieces 
     pub fn new() -> Self {
         use Color::*;
         use Piece::*;
-        Self {
-            
-            board: [
-                [
-                    Some((Bishop, White)),
-                    Some((Knight, White)),
-                    Some((Rook, White)),
-                    Some((Queen, White)),
-                    Some((King, White)),
-                    Some((Knight, White)),
-                    Some((Rook, White)),
-                    Some((Bishop, White)),
-                ],
-                [
-                    Some((Pawn, White)),
-                    Some((Pawn, White)),
-                    Some((Pawn, White)),
-                    Some((Pawn, White)),
-                    Some((Pawn, White)),
-                    Some((Pawn, White)),
-                    Some((Pawn, White)),
-                    Some((Pawn, White)),
-                ],
-                [None; 8],
-                [None; 8],
-                [None; 8],
-                [None; 8],
-                [
-                    Some((Pawn, Black)),
-                    Some((Pawn, Black)),
-                    Some((Pawn, Black)),
-                    Some((Pawn, Black)),
-                    Some((Pawn, Black)),
-                    Some((Pawn, Black)),
-                    Some((Pawn, Black)),
-                    Some((Pawn, Black)),
-                ],
-                [
-                    Some((Bishop, Black)),
-                    Some((Knight, Black)),
-                    Some((Queen, Black)),
-                    Some((Rook, Black)),
-                    Some((King, Black)),
-                    Some((Knight, Black)),
-                    Some((Rook, Black)),
-                    Some((Bishop, Black)),
-                ],
-            ],
+        let back_rank = [Rook, Knight, Bishop, Queen, King, Bishop, Knight, Rook];
+        let mut board = Board::empty();
+        for (file, piece) in File::ALL.into_iter().zip(back_rank) {
+            board.set(Square::new(file, Rank::First), Some((piece, White)));
+            board.set(Square::new(file, Rank::Second), Some((Pawn, White)));
+            board.set(Square::new(file, Rank::Seventh), Some((Pawn, Black)));
+            board.set(Square::new(file, Rank::Eighth), Some((piece, Black)));
+        }
+        let mut game = Self {
+            board,
             color: White,
-            promotion: Queen,
+            castling: CastlingRights::all(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
             gamestate: GameState::InProgress,
-        }
+            history: Vec::new(),
+            redo: Vec::new(),
+            hash: 0,
+            repetitions: HashMap::new(),
+        };
+        game.hash = game.full_hash();
+        game.reset_repetitions();
+        game
     }
 
 
@@ -98,563 +127,687 @@ impl Game {
         self.gamestate
     }
 
-    /// Checking for checks
-    /// We have to check if king is checked after every turn
-    /// and if it has been checked whether the king can escape or has been checkmated
-    /// the function gets the position of the king and loops through all the threats.
-    /// if the king has been checkmated the state will change to game over
-
-    fn check_checker(&self, position: &String, color: Color) -> GameState {
-        use GameState::*;
-        let mut output: GameState = InProgress;
-        let mut _index: HashSet<String> = HashSet::new();
-        let king_moves: Vec<String> = self.king_moves(&position).unwrap();
-        for rank in 0..=7 {
-            for file in 0..=7 {
-                let piece = self.piece_position(&self
-                    .index_to_string((rank, file)));
-                let piece_position = self.index_to_string((rank, file));
-                if piece != None {
-                    if piece.unwrap().1 != color {
-                        let possible_moves: Vec<String> = self.possible_move(&piece_position).unwrap();
-                        if possible_moves.contains(&position) {
-                            output = Check;
-                            _index.insert(possible_moves.into_iter().collect());
-                            _index.insert(piece_position);
-                        }
-                    }
-                }
-                else {
-                    _index.insert(piece_position);
-                }
-            }
-        }
-        if king_moves.iter().all(|king_move| _index
-            .contains(king_move)) && _index.contains(position) {
-            output = GameOver;
+    /// The result of the game, Ongoing until it has ended
+    pub fn outcome(&self) -> Outcome {
+        match self.gamestate {
+            GameState::GameOver(outcome) => outcome,
+            _ => Outcome::Ongoing,
         }
+    }
 
-        output
+    /// The given side gives up and the other side wins
+    pub fn resign(&mut self, color: Color) -> Result<GameState, ChessError> {
+        self.end_game(Outcome::win(color.opposite(), Reason::Resignation))
     }
 
-    /// If the current game state is in progress and the move is legal,
-    /// move a piece and return the resulting state of the game.
-    /// at all times the position of the king should be checked to see if the game is still 
-    /// in progress
-    /// the function also checks if the pawns have reached other 
-    /// side of the board in the index and promotes them
-    pub fn make_move(&mut self, from: String, to: String) -> Option<GameState> {
-        
-        use GameState::*;
-        let color = self.color;
-        let piece = self.piece_position(&from).unwrap().0;
-        if self.get_game_state() == GameState::InProgress {
-            if self.possible_move(&from).unwrap().contains(&to) {
-                let from_index = self.find_position(&from);
-                let to_index = self.find_position(&to);
-                self.board[from_index.0 as usize][from_index.1 as usize] =
-                    self.board[to_index.0 as usize][to_index.1 as usize];
-                if piece == Piece::Pawn {
-                    match color {
-                        Color::White => {
-                            if to_index.0 == 0 {
-                                self.promote(self.index_to_string(to_index));
-                            }
-                        }
-                        Color::Black => {
-                            if to_index.0 == 7 {
-                                self.promote(self.index_to_string(to_index));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        let king_position = self.king_position(color);
-        match self.check_checker(&king_position, color) {
-            InProgress => Some(InProgress),
-            Check => Some(Check),
-            GameOver => Some(GameOver),
+    /// The given side has run out of time and the other side wins,
+    /// unless the other side has too little material left to ever mate
+    pub fn time_out(&mut self, color: Color) -> Result<GameState, ChessError> {
+        if self.has_insufficient_material(color.opposite()) {
+            self.end_game(Outcome::Draw(Reason::InsufficientMaterial))
+        } else {
+            self.end_game(Outcome::win(color.opposite(), Reason::Timeout))
         }
     }
-    fn king_position(&self, color: Color) -> String {
-        let mut king_position: String = String::new();
-        use Piece::*;
-        for rank in 0..=7 {
-            for file in 0..=7 {
-                if self.board[rank][file] == None {
-                    continue;
-                } else if self.board[rank][file].unwrap() == (King, color) {
-                    king_position = self.index_to_string((rank, file));
-                }
-            }
+
+    /// Whether the color has too little left to ever checkmate the other side:
+    /// a lone king, or a king with a single bishop or knight against a lone king.
+    /// Against any other piece or pawn a bishop or knight can still mate
+    /// with the help of the pieces blocking the king
+    pub fn has_insufficient_material(&self, color: Color) -> bool {
+        let pieces = |side: Color| -> Vec<Piece> {
+            Square::all()
+                .filter_map(|square| self.piece_at(square))
+                .filter(|&(piece, owner)| owner == side && piece != Piece::King)
+                .map(|(piece, _)| piece)
+                .collect()
+        };
+        match pieces(color)[..] {
+            [] => true,
+            [piece] => (piece == Piece::Bishop || piece == Piece::Knight) && pieces(color.opposite()).is_empty(),
+            _ => false,
         }
-        king_position
     }
 
-    ///The function promotes a pawn into a new higher value piece
-    pub fn promote_piece(&mut self, piece: String) -> () {
-        use Piece::*;
-        match piece.as_ref() {
-            "Rook" => self.promotion = Rook,
-            "Bishop" => self.promotion = Bishop,
-            "Knight" => self.promotion = Knight,
-            "Queen" => self.promotion = Queen,
-            _ => self.promotion = Queen,
-        };
-        ()
-    }
-    fn promote(&mut self, position: String) {
-        let index = self.find_position(&position);
-        self.board[index.0][index.1] = Some((self.promotion, self.color));
-    }
-
-    fn piece_position(&self, position: &String) -> Option<(Piece, Color)> {
-        let position = self.find_position(position);
-        self.board[position.1 as usize][position.0 as usize]
-    }
-
-    fn find_position(&self, position: &String) -> (usize, usize) {
-        let _rank: char = position[..1].parse().ok().unwrap();
-        let _file: usize = position[1..].parse().ok().unwrap();
-        // Make arrays upside down
-        match self.color {
-            Color::White => {
-                let rank: usize = match _rank {
-                    'a' => 0,
-                    'b' => 1,
-                    'c' => 2,
-                    'd' => 3,
-                    'e' => 4,
-                    'f' => 5,
-                    'g' => 6,
-                    'h' => 7,
-                    _ => 0,
-                };
-                let file = _file - 1;
-                (rank, file)
-            }
-            Color::Black => {
-                let rank: usize = match _rank {
-                    'a' => 7,
-                    'b' => 6,
-                    'c' => 5,
-                    'd' => 4,
-                    'e' => 3,
-                    'f' => 2,
-                    'g' => 1,
-                    'h' => 0,
-                    _ => 0,
-                };
-                let file: usize = match _file {
-                    1 => 7,
-                    2 => 6,
-                    3 => 5,
-                    4 => 4,
-                    5 => 3,
-                    6 => 2,
-                    7 => 1,
-                    8 => 0,
-                    _ => 0,
-                };
-                (rank, file)
-                //Outside of scope is mapped to zero
-            }
+    /// Neither side can ever checkmate: king against king, a king with a single
+    /// bishop or knight against a lone king, or only bishops standing on
+    /// squares of the same color besides the kings
+    fn is_dead_position(&self) -> bool {
+        let pieces: Vec<(Piece, Square)> = Square::all()
+            .filter_map(|square| self.piece_at(square).map(|(piece, _)| (piece, square)))
+            .filter(|&(piece, _)| piece != Piece::King)
+            .collect();
+        match pieces[..] {
+            [] => true,
+            [(piece, _)] => piece == Piece::Bishop || piece == Piece::Knight,
+            [(_, first), ..] => pieces
+                .iter()
+                .all(|&(piece, square)| piece == Piece::Bishop && square.is_dark() == first.is_dark()),
         }
     }
 
+    /// Both sides agree to a draw
+    pub fn agree_draw(&mut self) -> Result<GameState, ChessError> {
+        self.end_game(Outcome::Draw(Reason::Agreement))
+    }
 
-    /// If a piece is standing on the given tile, return all possible
-    /// new positions of that piece. Don't forget to the rules for check.
-    pub fn possible_move(&self, position: &String) -> Option<Vec<String>> {
-        let piece = self.piece_position(&position);
-        use Piece::*;
-        if piece != None {
-            match piece.unwrap().0 {
-                King => self.king_moves(position),
-                Queen => self.queen_moves(position),
-                Bishop => self.bishop_moves(position),
-                Knight => self.knight_moves(position),
-                Rook => self.rook_moves(position),
-                Pawn => self.pawn_moves(position),
-            }
+    /// The reason the side to move could claim a draw right now, if any.
+    /// A draw can be claimed once 50 moves by each side have passed
+    /// without a capture or a pawn move, or when the same position
+    /// has been on the board three times
+    pub fn can_claim_draw(&self) -> Option<Reason> {
+        if self.outcome().is_over() {
+            None
+        } else if self.halfmove_clock >= FIFTY_MOVES {
+            Some(Reason::FiftyMoveRule)
+        } else if self.repetition_count() >= THREEFOLD {
+            Some(Reason::Repetition)
         } else {
             None
         }
     }
-    fn relative_position(&self, position: &String, rank: i8, file: i8) -> Option<String> {
-        let position = self.find_position(&position);
-        let mut output: (usize, usize) = (
-            (position.0 as i8 + file) as usize,
-            (position.1 as i8 + rank) as usize,
-        );
-        if position.0 as i8 + file < 0 {
-            output.0 = 0;
-        }
-        if position.0 as i8 + file > 7 {
-            output.0 = 7;
-        }
-        if position.1 as i8 + rank < 0 {
-            output.1 = 0;
-        }
-        if position.1 as i8 + rank > 7 {
-            output.1 = 7;
+
+    /// Ends the game as a draw if one can be claimed
+    pub fn claim_draw(&mut self) -> Result<GameState, ChessError> {
+        match self.can_claim_draw() {
+            Some(reason) => self.end_game(Outcome::Draw(reason)),
+            None if self.outcome().is_over() => Err(ChessError::GameOver),
+            None => Err(ChessError::NoDrawToClaim),
         }
+    }
 
-        Some(self.index_to_string(output))
+    /// A 64 bit Zobrist hash of the position covering the pieces, the side to move,
+    /// the castling rights and the en passant file. Positions that are the same
+    /// by the repetition rules get the same hash however they were reached
+    pub fn hash(&self) -> u64 {
+        self.hash
     }
 
-    fn index_to_string(&self, input: (usize, usize)) -> String {
-        let mut output: String = String::with_capacity(2);
-        output.push(match input.0 {
-            0 => 'a',
-            1 => 'b',
-            2 => 'c',
-            3 => 'd',
-            4 => 'e',
-            5 => 'f',
-            6 => 'g',
-            7 => 'h',
-            _ => ' ',
-        });
-        
-        output.push(char::from_digit(input.1 as u32 + 1, 10).unwrap_or(' '));
-        output
+    /// Computes the hash from scratch, for when the position has been set up directly
+    fn full_hash(&self) -> u64 {
+        let mut hash = KEYS.side(self.color) ^ self.castling_and_en_passant_key();
+        for square in Square::all() {
+            if let Some((piece, color)) = self.piece_at(square) {
+                hash ^= KEYS.piece(piece, color, square);
+            }
+        }
+        hash
+    }
 
-        //Panics if a piece is outside of board
+    /// The part of the hash for the castling rights and the en passant square,
+    /// the latter only counting when a pawn is standing next to it that could take
+    fn castling_and_en_passant_key(&self) -> u64 {
+        let en_passant = self.en_passant.filter(|&target| {
+            [1, -1].iter().any(|&file| {
+                target
+                    .offset(-self.forward(self.color), file)
+                    .map(|from| self.piece_at(from) == Some((Piece::Pawn, self.color)))
+                    .unwrap_or(false)
+            })
+        });
+        KEYS.castling(self.castling.bits()) ^ en_passant.map(|square| KEYS.en_passant(square)).unwrap_or(0)
     }
 
+    /// How many times the current position has been on the board this game
+    pub fn repetition_count(&self) -> u32 {
+        self.repetitions.get(&self.hash).copied().unwrap_or(0)
+    }
 
+    /// Forgets earlier positions and counts the current one once,
+    /// for when a position is set up instead of reached by moves
+    fn reset_repetitions(&mut self) {
+        self.repetitions.clear();
+        self.repetitions.insert(self.hash, 1);
+    }
 
+    /// Moves by both sides since the last capture or pawn move
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
 
-    ///In this part every potential legal move by every piece will be examined
-    /// Would be interested to know if this part of the structure in Game can 
-    /// be moved to lib_of_lib
-    
+    /// The number of the current move, starting at 1 and going up after Black has moved
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
 
-    ///Pawn
-    ///Pawn moves one move forward in normal movement
-    /// It can move two positions in initial position 
-    /// It can take other pieces by diagonal moves to left or right
-    fn pawn_moves(&self, position: &String) -> Option<Vec<String>> {
-        let mut output: Vec<String> = Vec::new();
-        
-        let basic_move = self.relative_position(position, 1, 0).unwrap();
-        if self.piece_position(&basic_move) == None {
-            output.push(basic_move);
+    fn end_game(&mut self, outcome: Outcome) -> Result<GameState, ChessError> {
+        if self.outcome().is_over() {
+            return Err(ChessError::GameOver);
         }
+        self.gamestate = GameState::GameOver(outcome);
+        self.redo.clear();
+        Ok(self.gamestate)
+    }
 
-        let first_move_double = self.relative_position(position, 2, 0).unwrap();
-        if self.find_position(position).0 == 7 && self.piece_position(&first_move_double) == None {
-            output.push(first_move_double);
-        };
-        
-        let take1 = self.relative_position(position, 1, 1).unwrap();
-        if self.piece_position(&take1) != None {
-            if self.piece_position(&take1).unwrap().1 != self.color {
-                output.push(take1);
+    /// Checking for checks
+    /// We have to check if king is checked after every turn
+    /// and if it has been checked whether the king can escape or has been checkmated.
+    /// The side to move is checkmated when it is in check and has no legal move left,
+    /// and stalemated when it has no legal move without being in check.
+    /// A position where nobody can mate anymore is a draw.
+    /// After 75 moves by each side without a capture or a pawn move,
+    /// or when a position has been on the board five times,
+    /// the game is drawn without anyone having to claim it, unless it was mate
+    fn check_checker(&self) -> GameState {
+        use GameState::*;
+        let color = self.color;
+        let in_check = self.in_check(color);
+        if self.legal_moves().is_empty() {
+            if in_check {
+                GameOver(Outcome::win(color.opposite(), Reason::Checkmate))
+            } else {
+                GameOver(Outcome::Draw(Reason::Stalemate))
             }
+        } else if self.is_dead_position() {
+            GameOver(Outcome::Draw(Reason::InsufficientMaterial))
+        } else if self.halfmove_clock >= SEVENTY_FIVE_MOVES {
+            GameOver(Outcome::Draw(Reason::FiftyMoveRule))
+        } else if self.repetition_count() >= FIVEFOLD {
+            GameOver(Outcome::Draw(Reason::Repetition))
+        } else if in_check {
+            Check
+        } else {
+            InProgress
         }
+    }
 
-        let take2 = self.relative_position(position, 1, -1).unwrap();
-        if self.piece_position(&take2) != None {
-            if self.piece_position(&take2).unwrap().1 != self.color
-                && &take2 != &self.relative_position(position, 1, -1).unwrap()
-            {
-                output.push(take2);
-            }
-        }
+    /// The color whose turn it is
+    pub fn side_to_move(&self) -> Color {
+        self.color
+    }
 
-        Some(output)
+    /// The piece standing on the square, if any
+    pub fn piece_at(&self, square: Square) -> Option<(Piece, Color)> {
+        self.board.piece_at(square)
     }
 
-    ///For pieces which can make slighlty more complex manuvers we use loops to 
-    /// check which legal positions are available
+    fn set_piece(&mut self, square: Square, piece: Option<(Piece, Color)>) {
+        for (piece, color) in [self.piece_at(square), piece].into_iter().flatten() {
+            self.hash ^= KEYS.piece(piece, color, square);
+        }
+        self.board.set(square, piece);
+    }
 
-    ///Rook
-    /// We check for all legal vertical and horizontal movements
-    fn rook_moves(&self, _position: &String) -> Option<Vec<String>> {
-        let pp = self.find_position(_position);
-        let mut output: Vec<String> = Vec::new();
+    /// If the current game state is in progress and the move is legal,
+    /// move a piece and return the resulting state of the game.
+    /// at all times the position of the king should be checked to see if the game is still 
+    /// in progress
+    /// A pawn reaching the other side of the board is promoted
+    /// to the piece given in the move, which must be there.
+    /// If the move is played the turn passes to the other side,
+    /// otherwise the error tells why it was refused
+    pub fn play(&mut self, chess_move: Move) -> Result<GameState, ChessError> {
+        if self.outcome().is_over() {
+            return Err(ChessError::GameOver);
+        }
+        let legal = self.find_legal(chess_move)?;
+        self.redo.clear();
+        Ok(self.push_move(legal))
+    }
 
-        for i in 1..(8 - pp.0 ) {
-            let right = self.relative_position(_position, 0, i as i8).unwrap();
-            if self.piece_position(&right) == None {
-                output.push(right);
-            } else {
-                output.push(right);
-                break;
-            }
+    /// The legal move with the squares and promotion of the given move,
+    /// filled in with the flags from the move generation
+    fn find_legal(&self, chess_move: Move) -> Result<Move, ChessError> {
+        match self.piece_at(chess_move.from) {
+            None => return Err(ChessError::EmptySquare(chess_move.from)),
+            Some((_, color)) if color != self.color => return Err(ChessError::WrongSide(chess_move.from)),
+            Some(_) => {}
         }
-        for i in 1..pp.1 {
-            let left = self.relative_position(_position, 0, -(i as i8)).unwrap();
-            if self.piece_position(&left) == None {
-                output.push(left);
-            } else {
-                output.push(left);
-                break;
-            }
+        let moves: Vec<Move> = self
+            .moves_from(chess_move.from)
+            .into_iter()
+            .filter(|legal| legal.to == chess_move.to)
+            .collect();
+        if chess_move.promotion.is_none() && moves.iter().any(|legal| legal.promotion.is_some()) {
+            return Err(ChessError::MissingPromotion(chess_move));
         }
-        for i in 1..(8 - pp.1) {
-            let down = self.relative_position(_position, i as i8, 0).unwrap();
-            if self.piece_position(&down) == None {
-                output.push(down);
-            } else {
-                output.push(down);
-                break;
-            }
+        moves
+            .into_iter()
+            .find(|legal| legal.same_as(&chess_move))
+            .ok_or(ChessError::IllegalMove(chess_move))
+    }
+
+    /// Plays a move known to be legal and keeps it in the history
+    fn push_move(&mut self, chess_move: Move) -> GameState {
+        let undo = self.apply_move(chess_move);
+        self.history.push(undo);
+        *self.repetitions.entry(self.hash).or_insert(0) += 1;
+        self.gamestate = self.check_checker();
+        self.gamestate
+    }
+
+    /// Takes back the last move and returns it, None if no move has been played.
+    /// The move can be played again with `redo_move` until another move is made.
+    /// A game ended off the board, by a resignation, a draw or a loss on time,
+    /// stays decided and gives None, while a mate or another end on the board can be taken back
+    pub fn undo_move(&mut self) -> Option<Move> {
+        if self.outcome().is_over() && !matches!(self.check_checker(), GameState::GameOver(_)) {
+            return None;
         }
-        for i in 1..pp.1 {
-            let up = self.relative_position(_position, -(i as i8), 0).unwrap();
-            if self.piece_position(&up) == None {
-                output.push(up);
-            } else {
-                output.push(up);
-                break;
+        let undo = self.history.pop()?;
+        if let Some(count) = self.repetitions.get_mut(&self.hash) {
+            *count -= 1;
+            if *count == 0 {
+                self.repetitions.remove(&self.hash);
             }
         }
-        Some(output)
+        self.unapply_move(undo);
+        self.redo.push(undo.chess_move);
+        Some(undo.chess_move)
     }
 
-
-    ///Knight 
-    ///Quite complicated since it needs to move in three horizontal/diagonal and
-    /// then two diagonal/horizontal and make a check for legality of the move
-    /// Still unsatisfied with this method and recieved a lot of help in coding it 
-    /// Inform me if you get any novel ideas
-    
-    fn knight_moves(&self, _position: &String) -> Option<Vec<String>> {
-        let mut output: Vec<String> = Vec::new();
-        let relative_rank: [i8; 8] = [1, 1, -1, -1, 2, 2, -2, -2];
-        let relative_file: [i8; 8] = [2, -2, 2, -2, 1, -1, 1, -1];
-        for i in 0..8 {
-            let next_position = self
-            .relative_position(_position, relative_rank[i], relative_file[i]).unwrap();
-            if self.piece_position(&next_position) == None || self.piece_position(&next_position)
-            .unwrap().1 != self.piece_position(&_position).unwrap().1
-            {
-                output.push(next_position);
-            }
+    /// Plays the last move taken back with `undo_move` again,
+    /// None if there is nothing to redo or the game is over
+    pub fn redo_move(&mut self) -> Option<GameState> {
+        if self.outcome().is_over() {
+            return None;
         }
-        Some(output)
+        let chess_move = self.redo.pop()?;
+        Some(self.push_move(chess_move))
     }
 
+    /// The moves played so far, the first move first
+    pub fn history(&self) -> Vec<Move> {
+        self.history.iter().map(|undo| undo.chess_move).collect()
+    }
 
-    ///Bishop 
-    /// Cross movements on the board
-    fn bishop_moves(&self, _position: &String) -> Option<Vec<String>> {
-        let position = self.find_position(_position);
-        let mut output: Vec<String> = Vec::new();
+    /// String version of `play` taking squares like "e2" and "e4".
+    /// Promotions need the piece, so they are played as `play("e7e8q".parse()?)`
+    pub fn make_move(&mut self, from: String, to: String) -> Result<GameState, ChessError> {
+        let from = self.find_position(&from)?;
+        let to = self.find_position(&to)?;
+        self.play(Move::new(from, to))
+    }
 
-        for i in 1..(8 - position.0) {
-            let up_right = self.relative_position(_position, -(i as i8), i as i8).unwrap();
-            if self.piece_position(&up_right) == None {
-                output.push(up_right);
-            } else {
-                output.push(up_right);
-                break;
-            }
+    fn king_position(&self, color: Color) -> Option<Square> {
+        bitboard::squares(self.board.pieces(Piece::King, color)).next()
+    }
+
+    /// Moves whatever stands on `from` to `to` without looking at the rules
+    fn move_piece(&mut self, from: Square, to: Square) {
+        self.set_piece(to, self.piece_at(from));
+        self.set_piece(from, None);
+    }
+
+    /// Plays a move on the board including everything that comes with it:
+    /// the rook jumping over the king when castling,
+    /// the pawn removed by an en passant capture, the promoted piece,
+    /// the square a double pushed pawn can be taken on,
+    /// the castling rights lost by moving a king or touching a corner,
+    /// the move counters and the turn passing to the other side.
+    /// Returns what is needed to take the move back with `unapply_move`
+    fn apply_move(&mut self, chess_move: Move) -> Undo {
+        let Move { from, to, promotion, flags } = chess_move;
+        let captured_square = if flags.en_passant {
+            Square::new(to.file(), from.rank())
+        } else {
+            to
+        };
+        let undo = Undo {
+            chess_move,
+            captured: self.piece_at(captured_square),
+            castling: self.castling,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            gamestate: self.gamestate,
+            hash: self.hash,
+        };
+        let (piece, color) = match self.piece_at(from) {
+            Some(moving) => moving,
+            None => return undo,
+        };
+        self.hash ^= self.castling_and_en_passant_key();
+        if piece == Piece::Pawn || flags.capture {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
         }
-        for i in 1..position.1 {
-            let up_left = self.relative_position(_position, -(i as i8), -(i as i8)).unwrap();
-            if self.piece_position(&up_left) == None {
-                output.push(up_left);
-            } else {
-                output.push(up_left);
-                break;
-            }
+        if color == Color::Black {
+            self.fullmove_number += 1;
         }
-        for i in 1..(8 - position.1) {
-            let down_right = self.relative_position(_position, i as i8, i as i8).unwrap();
-            if self.piece_position(&down_right) == None {
-                output.push(down_right);
-            } else {
-                output.push(down_right);
-                break;
-            }
+        if flags.castling {
+            let (rook_from, rook_to) = self.castling_rook(to);
+            self.move_piece(rook_from, rook_to);
         }
-        for i in 1..position.1 {
-            let down_left = self.relative_position(_position, i as i8, -(i as i8)).unwrap();
-            if self.piece_position(&down_left) == None {
-                output.push(down_left);
-            } else {
-                output.push(down_left);
-                break;
-            }
+        if flags.en_passant {
+            self.set_piece(captured_square, None);
+        }
+        if piece == Piece::King {
+            self.castling.revoke(color);
+        }
+        self.castling.revoke_corner(from);
+        self.castling.revoke_corner(to);
+        self.move_piece(from, to);
+        if let Some(piece) = promotion {
+            self.set_piece(to, Some((piece, color)));
+        }
+        self.en_passant = if flags.double_push {
+            from.offset(self.forward(color), 0)
+        } else {
+            None
+        };
+        self.color = color.opposite();
+        self.hash ^= KEYS.side(Color::Black) ^ self.castling_and_en_passant_key();
+        undo
+    }
+
+    /// Puts the board back the way it was before `apply_move`
+    fn unapply_move(&mut self, undo: Undo) {
+        let Move { from, to, promotion, flags } = undo.chess_move;
+        self.color = self.color.opposite();
+        self.move_piece(to, from);
+        if promotion.is_some() {
+            self.set_piece(from, Some((Piece::Pawn, self.color)));
+        }
+        if flags.en_passant {
+            self.set_piece(Square::new(to.file(), from.rank()), undo.captured);
+        } else {
+            self.set_piece(to, undo.captured);
+        }
+        if flags.castling {
+            let (rook_from, rook_to) = self.castling_rook(to);
+            self.move_piece(rook_to, rook_from);
+        }
+        self.castling = undo.castling;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.gamestate = undo.gamestate;
+        self.hash = undo.hash;
+    }
+
+    /// Where the rook comes from and goes to when the king castles to the square
+    fn castling_rook(&self, king_to: Square) -> (Square, Square) {
+        if king_to.file() == File::G {
+            (Square::new(File::H, king_to.rank()), Square::new(File::F, king_to.rank()))
+        } else {
+            (Square::new(File::A, king_to.rank()), Square::new(File::D, king_to.rank()))
         }
+    }
 
-        Some(output)
+    /// Turns a square like "e2" into a Square
+    fn find_position(&self, position: &str) -> Result<Square, ChessError> {
+        position.parse()
     }
 
 
-    ///Queen
-    /// We implement rook and bishop moves in queen
-    fn queen_moves(&self, _position: &String) -> Option<Vec<String>> {
-        let position = self.find_position(_position);
+    /// If a piece is standing on the given tile, return all possible
+    /// new positions of that piece. Don't forget to the rules for check.
+    /// Moves that would leave the own king attacked are filtered out,
+    /// which covers pinned pieces and moving the king into check
+    pub fn possible_move(&self, position: &str) -> Result<Vec<String>, ChessError> {
+        let from = self.find_position(position)?;
+        if self.piece_at(from).is_none() {
+            return Err(ChessError::EmptySquare(from));
+        }
         let mut output: Vec<String> = Vec::new();
-        for i in 1..(8 - position.0) {
-            let up_right = self.relative_position(_position, -(i as i8), i as i8).unwrap();
-            if self.piece_position(&up_right) == None {
-                output.push(up_right);
-            } else {
-                output.push(up_right);
-                break;
+        for legal in self.moves_from(from) {
+            let to = legal.to.to_string();
+            if !output.contains(&to) {
+                output.push(to);
             }
         }
-        for i in 1..position.1 {
-            let up_left = self.relative_position(_position, -(i as i8), -(i as i8)).unwrap();
-            if self.piece_position(&up_left) == None {
-                output.push(up_left);
-            } else {
-                output.push(up_left);
-                break;
-            }
+        Ok(output)
+    }
+
+    /// The legal moves of the piece standing on the square,
+    /// empty if there is none
+    pub fn moves_from(&self, square: Square) -> Vec<Move> {
+        self.pseudo_moves(square)
+            .into_iter()
+            .filter(|&chess_move| self.is_legal(chess_move))
+            .collect()
+    }
+
+    /// Every legal move of the side to move
+    pub fn legal_moves(&self) -> Vec<Move> {
+        bitboard::squares(self.board.color(self.color))
+            .flat_map(|square| self.moves_from(square))
+            .collect()
+    }
+
+    /// Moves the piece follows by its movement rules
+    /// without looking at the safety of its own king
+    fn pseudo_moves(&self, square: Square) -> Vec<Move> {
+        use Piece::*;
+        match self.piece_at(square) {
+            Some((King, color)) => self.king_moves(square, color),
+            Some((Queen, color)) => self.queen_moves(square, color),
+            Some((Bishop, color)) => self.bishop_moves(square, color),
+            Some((Knight, color)) => self.knight_moves(square, color),
+            Some((Rook, color)) => self.rook_moves(square, color),
+            Some((Pawn, color)) => self.pawn_moves(square, color),
+            None => Vec::new(),
         }
-        for i in 1..(8 - position.1) {
-            let down_right = self.relative_position(_position, i as i8, i as i8).unwrap();
-            if self.piece_position(&down_right) == None {
-                output.push(down_right);
-            } else {
-                output.push(down_right);
-                break;
-            }
+    }
+
+    /// A move is legal if the king of the moving side
+    /// is not attacked once the move has been made on a copy of the board
+    fn is_legal(&self, chess_move: Move) -> bool {
+        let color = match self.piece_at(chess_move.from) {
+            Some((_, color)) => color,
+            None => return false,
+        };
+        let mut after = self.position_only();
+        after.apply_move(chess_move);
+        !after.in_check(color)
+    }
+
+    /// A copy of the position without the game history,
+    /// for trying out moves without touching the game
+    fn position_only(&self) -> Game {
+        Game {
+            history: Vec::new(),
+            redo: Vec::new(),
+            repetitions: HashMap::new(),
+            ..*self
         }
-        for i in 1..position.1 {
-            let down_left = self.relative_position(_position, i as i8, -(i as i8)).unwrap();
-            if self.piece_position(&down_left) == None {
-                output.push(down_left);
-            } else {
-                output.push(down_left);
-                break;
-            }
+    }
+
+    fn in_check(&self, color: Color) -> bool {
+        match self.king_position(color) {
+            Some(king_position) => self.is_square_attacked(king_position, color.opposite()),
+            None => false,
         }
-        for i in 1..(8 - position.0 ) {
-            let right = self.relative_position(_position, 0, i as i8).unwrap();
-            if self.piece_position(&right) == None {
-                output.push(right);
-            } else {
-                output.push(right);
-                break;
-            }
+    }
+
+    /// Whether any piece of the given color attacks the square,
+    /// found by looking outwards from the square with the attack tables
+    fn is_square_attacked(&self, square: Square, by: Color) -> bool {
+        self.board.attackers(square, by) != 0
+    }
+
+    /// The rank direction the pawns of the color are walking in
+    fn forward(&self, color: Color) -> i8 {
+        match color {
+            Color::White => 1,
+            Color::Black => -1,
         }
-        for i in 1..position.1 {
-            let left = self.relative_position(_position, 0, -(i as i8)).unwrap();
-            if self.piece_position(&left) == None {
-                output.push(left);
-            } else {
-                output.push(left);
-                break;
+    }
+
+    /// A move to the square, marked as a capture if something stands there
+    fn move_to(&self, from: Square, to: Square) -> Move {
+        let mut chess_move = Move::new(from, to);
+        chess_move.flags.capture = self.board.occupied() & bitboard::bit(to) != 0;
+        chess_move
+    }
+
+    /// A move to every square of the mask that is not taken by an own piece
+    fn moves_to(&self, from: Square, color: Color, targets: u64) -> Vec<Move> {
+        bitboard::squares(targets & !self.board.color(color))
+            .map(|to| self.move_to(from, to))
+            .collect()
+    }
+
+
+
+
+    ///In this part every potential legal move by every piece will be examined
+    /// Would be interested to know if this part of the structure in Game can 
+    /// be moved to lib_of_lib
+    ///
+    ///Pawn
+    ///Pawn moves one move forward in normal movement
+    /// It can move two positions in initial position 
+    /// It can take other pieces by diagonal moves to left or right,
+    /// or a pawn that just passed it with a double move by en passant.
+    /// Reaching the last rank gives one move for every piece the pawn can become
+    fn pawn_moves(&self, square: Square, color: Color) -> Vec<Move> {
+        let forward = self.forward(color);
+        let (start_rank, last_rank) = match color {
+            Color::White => (Rank::Second, Rank::Eighth),
+            Color::Black => (Rank::Seventh, Rank::First),
+        };
+        let mut output: Vec<Move> = Vec::new();
+
+        if let Some(basic_move) = square.offset(forward, 0) {
+            if self.piece_at(basic_move).is_none() {
+                output.push(self.move_to(square, basic_move));
+                if square.rank() == start_rank {
+                    if let Some(first_move_double) = square.offset(2 * forward, 0) {
+                        if self.piece_at(first_move_double).is_none() {
+                            let mut double = self.move_to(square, first_move_double);
+                            double.flags.double_push = true;
+                            output.push(double);
+                        }
+                    }
+                }
             }
         }
-        for i in 1..(8 - position.1) {
-            let down = self.relative_position(_position, i as i8, 0).unwrap();
-            if self.piece_position(&down) == None {
-                output.push(down);
-            } else {
-                output.push(down);
-                break;
-            }
+
+        let attacks = bitboard::pawn_attacks(square, color);
+        output.extend(self.moves_to(square, color, attacks & self.board.color(color.opposite())));
+        if let Some(take) = self.en_passant.filter(|&take| color == self.color && attacks & bitboard::bit(take) != 0) {
+            let mut en_passant = self.move_to(square, take);
+            en_passant.flags.capture = true;
+            en_passant.flags.en_passant = true;
+            output.push(en_passant);
         }
-        for i in 1..position.1 {
-            let up = self.relative_position(_position, -(i as i8), 0).unwrap();
-            if self.piece_position(&up) == None {
-                output.push(up);
-            } else {
-                output.push(up);
-                break;
+
+        let mut promotions: Vec<Move> = Vec::new();
+        for chess_move in output.iter().filter(|chess_move| chess_move.to.rank() == last_rank) {
+            for piece in PROMOTIONS {
+                promotions.push(Move { promotion: Some(piece), ..*chess_move });
             }
         }
-        Some(output)
+        if promotions.is_empty() {
+            output
+        } else {
+            promotions
+        }
     }
 
+    // For pieces which can make slighlty more complex manuvers the squares
+    // they reach come from the attack tables and rays of the bitboards,
+    // leaving out the squares of their own pieces
 
-    ///King 
-    /// GameState and check should be taken into consideration
-    /// surrounding positions of the king are checked with the 
-    /// implementation of check checker
-
-    fn king_moves(&self, _position: &String) -> Option<Vec<String>> {
-
-        let mut output: Vec<String> = Vec::with_capacity(8);
-        if self.piece_position(&_position) != None {
-            let color: Color = self.piece_position(&_position).unwrap().1;
-            for d in -1..=1 {
-                for h in -1..=1 {
-                    let possible_position = self.relative_position(_position, d, h).unwrap();
-                    if _position != &possible_position
-                        && self.piece_position(&possible_position) == None
-                        && self.check_checker(&possible_position, color) == GameState::InProgress
-                    {
-                        output.push(possible_position);
-                    }
-                }
-            }
-        }
-        Some(output)
+    ///Rook
+    /// We check for all legal vertical and horizontal movements
+    fn rook_moves(&self, square: Square, color: Color) -> Vec<Move> {
+        self.moves_to(square, color, bitboard::rook_attacks(square, self.board.occupied()))
     }
 
 
+    ///Knight 
+    ///Quite complicated since it needs to move in three horizontal/diagonal and
+    /// then two diagonal/horizontal and make a check for legality of the move
+    fn knight_moves(&self, square: Square, color: Color) -> Vec<Move> {
+        self.moves_to(square, color, bitboard::knight_attacks(square))
+    }
+
 
+    ///Bishop 
+    /// Cross movements on the board
+    fn bishop_moves(&self, square: Square, color: Color) -> Vec<Move> {
+        self.moves_to(square, color, bitboard::bishop_attacks(square, self.board.occupied()))
+    }
 
 
-/// Implement print routine for Game.
-///
-/// Output example:
-/// |:----------------------:|
-/// | R  Kn B  K  Q  B  Kn R |
-/// | P  P  P  P  P  P  P  P |
-/// | *  *  *  *  *  *  *  * |
-/// | *  *  *  *  *  *  *  * |
-/// | *  *  *  *  *  *  *  * |
-/// | *  *  *  *  *  *  *  * |
-/// | P  P  P  P  P  P  P  P |
-/// | R  Kn B  K  Q  B  Kn R |
-/// |:----------------------:|
+    ///Queen
+    /// We implement rook and bishop moves in queen
+    fn queen_moves(&self, square: Square, color: Color) -> Vec<Move> {
+        let mut output = self.rook_moves(square, color);
+        output.extend(self.bishop_moves(square, color));
+        output
+    }
 
 
-    fn board_output(&self, input: Option<(Piece, Color)>) -> String {
-        use Color::*;
-        use Piece::*;
-        match input {
-            Some((Pawn, White)) => format!("{}", "WP"),
-            Some((Rook, White)) => format!("{}", "WR"),
-            Some((Knight, White)) => format!("{}", "WKn"),
-            Some((Bishop, White)) => format!("{}", "WB"),
-            Some((Queen, White)) => format!("{}", "WQ"),
-            Some((King, White)) => format!("{}", "WK"),
+    ///King 
+    /// The surrounding positions of the king, whether they are
+    /// attacked is left to the legality check
+    fn king_moves(&self, square: Square, color: Color) -> Vec<Move> {
+        let mut output = self.moves_to(square, color, bitboard::king_attacks(square));
+        output.extend(self.castling_moves(square, color));
+        output
+    }
 
-            Some((Pawn, Black)) => format!("{}", "BP"),
-            Some((Rook, Black)) => format!("{}", "BR"),
-            Some((Knight, Black)) => format!("{}", "BKn"),
-            Some((Bishop, Black)) => format!("{}", "BB"),
-            Some((Queen, Black)) => format!("{}", "BQ"),
-            Some((King, Black)) => format!("{}", "BK"),
-            None => format!("{}", "*"),
+    /// Castling is written as the king moving two squares towards the rook.
+    /// The king and the rook must still have their rights, every square between them
+    /// must be empty and the king may not castle out of, through or into check
+    fn castling_moves(&self, square: Square, color: Color) -> Vec<Move> {
+        let mut output: Vec<Move> = Vec::new();
+        let rank = match color {
+            Color::White => Rank::First,
+            Color::Black => Rank::Eighth,
+        };
+        if square != Square::new(File::E, rank) || self.is_square_attacked(square, color.opposite()) {
+            return output;
+        }
+        let sides = [
+            (self.castling.king_side(color), File::H, &[File::F, File::G][..], &[File::F, File::G][..]),
+            (self.castling.queen_side(color), File::A, &[File::B, File::C, File::D][..], &[File::D, File::C][..]),
+        ];
+        for (right, rook_file, between, passed) in sides {
+            let is_empty = |file: &File| self.piece_at(Square::new(*file, rank)).is_none();
+            let is_safe = |file: &File| !self.is_square_attacked(Square::new(*file, rank), color.opposite());
+            if right
+                && self.piece_at(Square::new(rook_file, rank)) == Some((Piece::Rook, color))
+                && between.iter().all(is_empty)
+                && passed.iter().all(is_safe)
+            {
+                let mut castle = Move::new(square, Square::new(passed[1], rank));
+                castle.flags.castling = true;
+                output.push(castle);
+            }
         }
+        output
     }
 
-    pub fn print(&self) {
-        let mut output: String = String::new();
-        let board = self.board;
-        output.push_str("|:----------------------:|");
-        output.push_str(" A B C D E F G H \n");
-        for rank in 0..7 {
-            for file in 0..7 {
-                output.push_str("|");
-                if file == 7 {
-                    output.push_str(&rank.to_string());
-                    output.push_str("\n");
-                }
-                output += &self.board_output(board[2][2]);
-                if rank == 7 {
-                    output.push_str("")
-                }
+    /// An 8x8 diagram of the board with the ranks and files written along the edges,
+    /// seen from the side of the given color. Pieces are written as in FEN,
+    /// uppercase for White, and empty squares as dots
+    pub fn diagram(&self, bottom: Color) -> String {
+        let mut ranks = Rank::ALL;
+        let mut files = File::ALL;
+        match bottom {
+            Color::White => ranks.reverse(),
+            Color::Black => files.reverse(),
+        }
+        let mut output = String::new();
+        for rank in ranks {
+            output.push(rank.to_char());
+            for file in files {
+                output.push(' ');
+                output.push(match self.piece_at(Square::new(file, rank)) {
+                    Some((piece, color)) => fen::piece_char(piece, color),
+                    None => '.',
+                });
             }
-            output.push_str("|:----------------------:|");
+            output.push('\n');
+        }
+        output.push(' ');
+        for file in files {
+            output.push(' ');
+            output.push(file.to_char());
         }
+        output
     }
 
-
+    /// Prints the board to the terminal with White at the bottom
+    pub fn print(&self) {
+        println!("{}", self);
+    }
 }
 
 impl Default for Game {
@@ -662,13 +815,27 @@ impl Default for Game {
         Self::new()
     }
 }
-impl fmt::Debug for Game {
+/// The diagram of the board with White at the bottom,
+/// or with Black at the bottom when written with `{:#}`
+impl fmt::Display for Game {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "")}
+        let bottom = if f.alternate() { Color::Black } else { Color::White };
+        write!(f, "{}", self.diagram(bottom))
     }
+}
 
-    #[test]
-    fn it_works() {
+/// The board together with everything else that makes up the position
+impl fmt::Debug for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.diagram(Color::White))?;
+        writeln!(f, "Side to move: {:?}", self.color)?;
+        writeln!(f, "Castling: {}", self.castling)?;
+        write!(f, "FEN: {}", self.to_fen())
+    }
+}
+
+    #[test]
+    fn it_works() {
         assert_eq!(2 + 2, 4);
     }
     #[test]
@@ -679,3 +846,1018 @@ impl fmt::Debug for Game {
 
         assert_eq!(game.get_game_state(), GameState::InProgress);
     }
+
+    /// An empty board with only the given pieces placed on it
+    #[cfg(test)]
+    fn setup(pieces: &[(&str, Piece, Color)]) -> Game {
+        let mut game = Game::new();
+        game.board = Board::empty();
+        for (position, piece, color) in pieces {
+            game.set_piece(square(position), Some((*piece, *color)));
+        }
+        game.hash = game.full_hash();
+        game.reset_repetitions();
+        game
+    }
+
+    #[cfg(test)]
+    fn square(name: &str) -> Square {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn pinned_piece_cannot_leave_the_pin() {
+        use Color::*;
+        use Piece::*;
+        let game = setup(&[("e1", King, White), ("e2", Rook, White), ("e8", Rook, Black)]);
+
+        let moves = game.possible_move("e2").unwrap();
+
+        assert!(moves.iter().all(|to| to.starts_with('e')));
+        assert!(moves.contains(&"e8".to_string()));
+    }
+
+    #[test]
+    fn king_cannot_step_into_attack() {
+        use Color::*;
+        use Piece::*;
+        let game = setup(&[("e1", King, White), ("d8", Rook, Black), ("h8", King, Black)]);
+
+        let moves = game.possible_move("e1").unwrap();
+
+        assert!(!moves.contains(&"d1".to_string()));
+        assert!(!moves.contains(&"d2".to_string()));
+        assert!(moves.contains(&"f2".to_string()));
+    }
+
+    #[test]
+    fn only_check_evasions_are_legal() {
+        use Color::*;
+        use Piece::*;
+        let game = setup(&[
+            ("e1", King, White),
+            ("a2", Rook, White),
+            ("b1", Knight, White),
+            ("e8", Queen, Black),
+            ("h8", King, Black),
+        ]);
+
+        let mut moves: Vec<String> = game.legal_moves().iter().map(|legal| legal.to_string()).collect();
+        moves.sort();
+
+        assert_eq!(moves, vec!["a2e2", "e1d1", "e1d2", "e1f1", "e1f2"]);
+    }
+
+    #[test]
+    fn pinned_bishop_stays_on_the_diagonal() {
+        use Color::*;
+        use Piece::*;
+        let game = setup(&[("a1", King, White), ("b2", Bishop, White), ("h8", Bishop, Black)]);
+
+        assert_eq!(game.possible_move("b2").unwrap(), vec!["c3", "d4", "e5", "f6", "g7", "h8"]);
+    }
+
+    #[test]
+    fn castling_moves_king_and_rook() {
+        use Color::*;
+        use Piece::*;
+        let mut game = setup(&[("e1", King, White), ("h1", Rook, White), ("a1", Rook, White), ("e8", King, Black)]);
+
+        assert!(game.possible_move("e1").unwrap().contains(&"c1".to_string()));
+        game.make_move("e1".to_string(), "g1".to_string()).unwrap();
+
+        assert_eq!(game.piece_at(square("g1")), Some((King, White)));
+        assert_eq!(game.piece_at(square("f1")), Some((Rook, White)));
+        assert_eq!(game.piece_at(square("h1")), None);
+        assert!(!game.castling.king_side(White) && !game.castling.queen_side(White));
+    }
+
+    #[test]
+    fn no_castling_through_check_or_with_pieces_between() {
+        use Color::*;
+        use Piece::*;
+        let game = setup(&[
+            ("e1", King, White),
+            ("h1", Rook, White),
+            ("a1", Rook, White),
+            ("b1", Knight, White),
+            ("f8", Rook, Black),
+            ("e8", King, Black),
+        ]);
+
+        let moves = game.possible_move("e1").unwrap();
+
+        assert!(!moves.contains(&"g1".to_string()));
+        assert!(!moves.contains(&"c1".to_string()));
+    }
+
+    #[test]
+    fn moved_or_captured_rook_loses_castling_right() {
+        use Color::*;
+        use Piece::*;
+        let mut game = setup(&[
+            ("e1", King, White),
+            ("h1", Rook, White),
+            ("a1", Rook, White),
+            ("a8", Rook, Black),
+            ("e8", King, Black),
+            ("h7", Pawn, Black),
+        ]);
+
+        game.make_move("h1".to_string(), "h2".to_string()).unwrap();
+        game.make_move("h7".to_string(), "h6".to_string()).unwrap();
+        game.make_move("h2".to_string(), "h1".to_string()).unwrap();
+        game.make_move("h6".to_string(), "h5".to_string()).unwrap();
+        game.make_move("a1".to_string(), "a8".to_string()).unwrap();
+
+        assert!(!game.castling.king_side(White));
+        assert!(!game.castling.queen_side(White));
+        assert!(!game.castling.queen_side(Black));
+        assert!(game.castling.king_side(Black));
+        assert!(!game.possible_move("e1").unwrap().contains(&"g1".to_string()));
+    }
+
+    #[test]
+    fn en_passant_capture_removes_the_passed_pawn() {
+        use Color::*;
+        use Piece::*;
+        let mut game = setup(&[("e1", King, White), ("e2", Pawn, White), ("d4", Pawn, Black), ("h8", King, Black)]);
+        game.make_move("e2".to_string(), "e4".to_string()).unwrap();
+
+        assert_eq!(game.en_passant, Some(square("e3")));
+        assert!(game.possible_move("d4").unwrap().contains(&"e3".to_string()));
+        game.make_move("d4".to_string(), "e3".to_string()).unwrap();
+
+        assert_eq!(game.piece_at(square("e3")), Some((Pawn, Black)));
+        assert_eq!(game.piece_at(square("e4")), None);
+        assert_eq!(game.en_passant, None);
+    }
+
+    #[test]
+    fn only_the_side_to_move_can_take_en_passant() {
+        let mut game = Game::new();
+        game.play("e2e4".parse().unwrap()).unwrap();
+
+        assert_eq!(game.possible_move("d2").unwrap(), ["d3", "d4"]);
+        assert_eq!(game.possible_move("f2").unwrap(), ["f3", "f4"]);
+        assert!(game.moves_from(square("d2")).iter().all(|chess_move| !chess_move.flags.en_passant));
+    }
+
+    #[test]
+    fn en_passant_is_illegal_when_it_exposes_the_king() {
+        use Color::*;
+        use Piece::*;
+        let mut game = setup(&[
+            ("a5", King, White),
+            ("b5", Pawn, White),
+            ("c7", Pawn, Black),
+            ("h5", Rook, Black),
+            ("h8", King, Black),
+        ]);
+        game.color = Black;
+        game.make_move("c7".to_string(), "c5".to_string()).unwrap();
+
+        assert_eq!(game.en_passant, Some(square("c6")));
+        assert!(!game.possible_move("b5").unwrap().contains(&"c6".to_string()));
+    }
+
+    #[test]
+    fn sides_take_turns() {
+        use Color::*;
+        use Piece::*;
+        let mut game = setup(&[("e1", King, White), ("a2", Pawn, White), ("e8", King, Black), ("h7", Pawn, Black)]);
+
+        assert_eq!(game.side_to_move(), White);
+        assert_eq!(game.make_move("h7".to_string(), "h6".to_string()), Err(ChessError::WrongSide(square("h7"))));
+        assert!(game.make_move("a2".to_string(), "a3".to_string()).is_ok());
+        assert_eq!(game.side_to_move(), Black);
+        assert_eq!(game.make_move("a3".to_string(), "a4".to_string()), Err(ChessError::WrongSide(square("a3"))));
+        assert!(game.make_move("h7".to_string(), "h5".to_string()).is_ok());
+        assert_eq!(game.side_to_move(), White);
+    }
+
+    #[test]
+    fn resignation_ends_the_game() {
+        use Color::*;
+        use Piece::*;
+        let mut game = setup(&[("e1", King, White), ("a2", Pawn, White), ("e8", King, Black)]);
+
+        assert_eq!(game.outcome(), Outcome::Ongoing);
+        game.resign(White).unwrap();
+
+        assert_eq!(game.outcome(), Outcome::BlackWins(Reason::Resignation));
+        assert_eq!(game.outcome().winner(), Some(Black));
+        assert_eq!(game.agree_draw(), Err(ChessError::GameOver));
+        assert_eq!(game.make_move("a2".to_string(), "a3".to_string()), Err(ChessError::GameOver));
+    }
+
+    #[test]
+    fn back_rank_checkmate_ends_the_game() {
+        use Color::*;
+        use Piece::*;
+        let mut game = setup(&[
+            ("a1", Rook, White),
+            ("e1", King, White),
+            ("g8", King, Black),
+            ("f7", Pawn, Black),
+            ("g7", Pawn, Black),
+            ("h7", Pawn, Black),
+        ]);
+
+        let state = game.make_move("a1".to_string(), "a8".to_string()).unwrap();
+
+        assert_eq!(state, GameState::GameOver(Outcome::WhiteWins(Reason::Checkmate)));
+        assert_eq!(game.outcome().reason(), Some(Reason::Checkmate));
+    }
+
+    #[test]
+    fn check_that_can_be_blocked_is_not_mate() {
+        use Color::*;
+        use Piece::*;
+        let mut game = setup(&[
+            ("a1", Rook, White),
+            ("e1", King, White),
+            ("g8", King, Black),
+            ("f7", Pawn, Black),
+            ("g7", Pawn, Black),
+            ("h7", Pawn, Black),
+            ("c6", Bishop, Black),
+        ]);
+
+        assert_eq!(game.make_move("a1".to_string(), "a8".to_string()), Ok(GameState::Check));
+    }
+
+    #[test]
+    fn stalemate_is_a_draw() {
+        use Color::*;
+        use Piece::*;
+        let mut game = setup(&[("b6", King, White), ("c1", Queen, White), ("a8", King, Black)]);
+
+        let state = game.make_move("c1".to_string(), "c7".to_string()).unwrap();
+
+        assert_eq!(state, GameState::GameOver(Outcome::Draw(Reason::Stalemate)));
+    }
+
+    #[test]
+    fn squares_and_moves_read_and_write_as_text() {
+        assert_eq!(square("a1").index(), 0);
+        assert_eq!(square("h8").index(), 63);
+        assert_eq!(Square::new(File::E, Rank::Fourth).to_string(), "e4");
+        assert!("z9".parse::<Square>().is_err());
+        assert!("".parse::<Square>().is_err());
+        assert!("e44".parse::<Square>().is_err());
+
+        let promotion: Move = "e7e8q".parse().unwrap();
+        assert_eq!(promotion, Move::with_promotion(square("e7"), square("e8"), Piece::Queen));
+        assert_eq!(promotion.to_string(), "e7e8q");
+        assert!("e7e8x".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn typed_moves_can_be_played() {
+        use Color::*;
+        use Piece::*;
+        let mut game = setup(&[("e1", King, White), ("g1", Knight, White), ("a1", Rook, White), ("e8", King, Black)]);
+
+        assert_eq!(game.moves_from(square("g1")).len(), 3);
+        assert_eq!(game.play("g1f3".parse().unwrap()), Ok(GameState::InProgress));
+        assert_eq!(game.piece_at(square("f3")), Some((Knight, White)));
+        assert!(game.play("f3g5".parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn bad_input_is_reported_instead_of_panicking() {
+        let mut game = Game::new();
+
+        assert_eq!(game.make_move("z9".to_string(), "e4".to_string()), Err(ChessError::InvalidSquare("z9".to_string())));
+        assert_eq!(game.make_move("".to_string(), "e4".to_string()), Err(ChessError::InvalidSquare("".to_string())));
+        assert_eq!(game.make_move("e4".to_string(), "e5".to_string()), Err(ChessError::EmptySquare(square("e4"))));
+        assert_eq!(
+            game.make_move("e2".to_string(), "e5".to_string()),
+            Err(ChessError::IllegalMove(Move::new(square("e2"), square("e5"))))
+        );
+        assert_eq!(game.possible_move("e4"), Err(ChessError::EmptySquare(square("e4"))));
+        assert!(game.possible_move("i1").is_err());
+    }
+
+    #[test]
+    fn fen_round_trips() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        ] {
+            assert_eq!(Game::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn fen_counters_follow_the_moves() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w Q - 0 1").unwrap();
+
+        game.make_move("a1".to_string(), "a2".to_string()).unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/R3P3/4K3 b - - 1 1");
+        game.make_move("e8".to_string(), "d8".to_string()).unwrap();
+        game.make_move("e2".to_string(), "e4".to_string()).unwrap();
+        assert_eq!(game.to_fen(), "3k4/8/8/8/4P3/8/R7/4K3 b - e3 0 2");
+    }
+
+    #[test]
+    fn malformed_fen_is_rejected() {
+        for fen in [
+            "",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQxq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e4 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0",
+            "rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 b - e3 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPPPPPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/4N3/PPPP1PPP/RNBQKB1R b KQkq e3 0 1",
+            "rnbqkbnr/pppppppp/44/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "k7/8/8/8/8/8/8/K6R w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R4K1R w K - 0 1",
+            "r3k1r1/8/8/8/8/8/8/R3K2R b k - 0 1",
+        ] {
+            assert!(matches!(Game::from_fen(fen), Err(ChessError::InvalidFen(_))), "{}", fen);
+        }
+        assert_eq!(
+            Game::from_fen("8/8/8 w -").unwrap_err().to_string(),
+            "invalid FEN: expected 4 or 6 fields, found 3"
+        );
+        assert_eq!(
+            Game::from_fen("4k3/8/8/8/8/8/8/4K3 b - e3 0 1").unwrap_err().to_string(),
+            "invalid FEN: no pawn can just have passed over e3"
+        );
+        assert_eq!(
+            Game::from_fen("k7/8/8/8/8/8/8/K6R w KQkq - 0 1").unwrap_err().to_string(),
+            "invalid FEN: castling right K needs a king on e1 and a rook on h1"
+        );
+    }
+
+    #[test]
+    fn new_game_has_the_standard_setup() {
+        let game = Game::new();
+
+        assert_eq!(game.to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(game.legal_moves().len(), 20);
+    }
+
+    #[test]
+    fn black_moves_are_generated_the_same_way() {
+        let mut game = Game::new();
+        game.make_move("e2".to_string(), "e4".to_string()).unwrap();
+
+        assert_eq!(game.legal_moves().len(), 20);
+        assert_eq!(game.possible_move("g8").unwrap(), vec!["f6", "h6"]);
+        game.make_move("e7".to_string(), "e5".to_string()).unwrap();
+        assert_eq!(game.legal_moves().len(), 29);
+    }
+
+    #[test]
+    fn promotion_piece_comes_with_the_move() {
+        use Color::*;
+        use Piece::*;
+        let mut game = setup(&[("e1", King, White), ("b7", Pawn, White), ("a8", Rook, Black), ("h8", King, Black)]);
+
+        let promotions: Vec<String> = game.moves_from(square("b7")).iter().map(|legal| legal.to_string()).collect();
+        assert_eq!(promotions.len(), 8);
+        assert!(promotions.contains(&"b7a8n".to_string()));
+        assert!(promotions.contains(&"b7b8b".to_string()));
+        assert_eq!(game.possible_move("b7").unwrap(), vec!["b8", "a8"]);
+
+        assert_eq!(
+            game.make_move("b7".to_string(), "b8".to_string()),
+            Err(ChessError::MissingPromotion(Move::new(square("b7"), square("b8"))))
+        );
+        assert!(game.play("b7b8k".parse().unwrap()).is_err());
+        game.play("b7a8n".parse().unwrap()).unwrap();
+        assert_eq!(game.piece_at(square("a8")), Some((Knight, White)));
+    }
+
+    #[test]
+    fn undo_restores_every_kind_of_move() {
+        let start = "r3k2r/1P4p1/8/3Pp3/8/8/8/R3K2R w KQkq e6 4 20";
+        let mut game = Game::from_fen(start).unwrap();
+        let moves = ["d5e6", "e8g8", "b7a8q", "g7g5", "e1c1"];
+        for text in moves {
+            game.play(text.parse().unwrap()).unwrap();
+        }
+        let end = game.to_fen();
+
+        assert_eq!(game.history().iter().map(|played| played.to_string()).collect::<Vec<_>>(), moves);
+        while game.undo_move().is_some() {}
+        assert_eq!(game.to_fen(), start);
+        assert!(game.history().is_empty());
+
+        while game.redo_move().is_some() {}
+        assert_eq!(game.to_fen(), end);
+    }
+
+    #[test]
+    fn new_move_clears_the_redo_stack() {
+        let mut game = Game::new();
+        game.make_move("e2".to_string(), "e4".to_string()).unwrap();
+        assert_eq!(game.undo_move().map(|undone| undone.to_string()), Some("e2e4".to_string()));
+
+        game.make_move("d2".to_string(), "d4".to_string()).unwrap();
+        assert_eq!(game.redo_move(), None);
+        assert_eq!(game.history().len(), 1);
+    }
+
+    #[test]
+    fn ending_the_game_clears_the_redo_stack() {
+        let mut game = Game::new();
+        game.make_move("e2".to_string(), "e4".to_string()).unwrap();
+        game.undo_move();
+        game.resign(Color::White).unwrap();
+
+        assert_eq!(game.redo_move(), None);
+        assert_eq!(game.outcome(), Outcome::BlackWins(Reason::Resignation));
+        assert!(game.history().is_empty());
+    }
+
+    #[test]
+    fn a_game_ended_off_the_board_cannot_be_taken_back() {
+        let mut game = Game::new();
+        game.make_move("e2".to_string(), "e4".to_string()).unwrap();
+        game.resign(Color::Black).unwrap();
+
+        assert_eq!(game.undo_move(), None);
+        assert_eq!(game.outcome(), Outcome::WhiteWins(Reason::Resignation));
+        assert_eq!(game.history().len(), 1);
+
+        let mut game = Game::new();
+        for (from, to) in [("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")] {
+            game.make_move(from.to_string(), to.to_string()).unwrap();
+        }
+        assert_eq!(game.outcome(), Outcome::BlackWins(Reason::Checkmate));
+        assert!(game.undo_move().is_some());
+        assert_eq!(game.outcome(), Outcome::Ongoing);
+    }
+
+    #[test]
+    fn fifty_move_draw_can_be_claimed_and_seventy_five_ends_the_game() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 98 70").unwrap();
+        assert_eq!(game.claim_draw(), Err(ChessError::NoDrawToClaim));
+
+        game.make_move("a1".to_string(), "a2".to_string()).unwrap();
+        game.make_move("e8".to_string(), "d8".to_string()).unwrap();
+        assert_eq!((game.halfmove_clock(), game.fullmove_number()), (100, 71));
+        assert_eq!(game.can_claim_draw(), Some(Reason::FiftyMoveRule));
+        assert_eq!(game.claim_draw(), Ok(GameState::GameOver(Outcome::Draw(Reason::FiftyMoveRule))));
+
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 149 90").unwrap();
+        let state = game.make_move("a1".to_string(), "a2".to_string()).unwrap();
+        assert_eq!(state, GameState::GameOver(Outcome::Draw(Reason::FiftyMoveRule)));
+    }
+
+    #[test]
+    fn capture_or_pawn_move_resets_the_halfmove_clock() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/r3P3/R3K3 w - - 40 30").unwrap();
+
+        game.make_move("a1".to_string(), "a2".to_string()).unwrap();
+        assert_eq!(game.halfmove_clock(), 0);
+        game.make_move("e8".to_string(), "d8".to_string()).unwrap();
+        assert_eq!(game.halfmove_clock(), 1);
+        game.make_move("e2".to_string(), "e3".to_string()).unwrap();
+        assert_eq!(game.halfmove_clock(), 0);
+    }
+
+    #[cfg(test)]
+    fn shuffle_knights(game: &mut Game) {
+        for text in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            game.play(text.parse().unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn threefold_repetition_can_be_claimed() {
+        let mut game = Game::new();
+        shuffle_knights(&mut game);
+        assert_eq!(game.repetition_count(), 2);
+        assert_eq!(game.can_claim_draw(), None);
+
+        shuffle_knights(&mut game);
+        assert_eq!(game.repetition_count(), 3);
+        assert_eq!(game.can_claim_draw(), Some(Reason::Repetition));
+
+        game.undo_move();
+        game.undo_move();
+        assert_eq!(game.repetition_count(), 2);
+        assert_eq!(game.can_claim_draw(), None);
+    }
+
+    #[test]
+    fn fivefold_repetition_ends_the_game() {
+        let mut game = Game::new();
+        for _ in 0..3 {
+            shuffle_knights(&mut game);
+        }
+        assert_eq!(game.outcome(), Outcome::Ongoing);
+
+        shuffle_knights(&mut game);
+        assert_eq!(game.outcome(), Outcome::Draw(Reason::Repetition));
+    }
+
+    #[test]
+    fn en_passant_square_only_counts_when_it_can_be_taken() {
+        let mut game = Game::new();
+        game.make_move("e2".to_string(), "e4".to_string()).unwrap();
+        let without = Game::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(game.hash(), without.hash());
+
+        let with = Game::from_fen("rnbqkbnr/pppppppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        let without = Game::from_fen("rnbqkbnr/pppppppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_ne!(with.hash(), without.hash());
+    }
+
+    #[test]
+    fn dead_positions_are_drawn() {
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K1n1 w - - 0 1",
+            "2b1k3/8/8/8/8/8/8/4KB2 w - - 0 1",
+        ] {
+            let game = Game::from_fen(fen).unwrap();
+            assert_eq!(game.outcome(), Outcome::Draw(Reason::InsufficientMaterial), "{}", fen);
+        }
+        for fen in [
+            "1b2k3/8/8/8/8/8/8/4KB2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3NKN2 w - - 0 1",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+        ] {
+            assert_eq!(Game::from_fen(fen).unwrap().outcome(), Outcome::Ongoing, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn capturing_the_last_piece_draws() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4r3/4K3 w - - 0 1").unwrap();
+
+        let state = game.make_move("e1".to_string(), "e2".to_string()).unwrap();
+
+        assert_eq!(state, GameState::GameOver(Outcome::Draw(Reason::InsufficientMaterial)));
+    }
+
+    #[test]
+    fn timeout_against_a_lone_king_is_a_draw() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        assert!(game.has_insufficient_material(Color::Black));
+        assert!(!game.has_insufficient_material(Color::White));
+
+        game.time_out(Color::White).unwrap();
+        assert_eq!(game.outcome(), Outcome::Draw(Reason::InsufficientMaterial));
+
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        game.time_out(Color::Black).unwrap();
+        assert_eq!(game.outcome(), Outcome::WhiteWins(Reason::Timeout));
+
+        let mut game = Game::from_fen("4k3/pppppppp/8/8/8/8/8/4KN2 w - - 0 1").unwrap();
+        assert!(!game.has_insufficient_material(Color::White));
+        game.time_out(Color::Black).unwrap();
+        assert_eq!(game.outcome(), Outcome::WhiteWins(Reason::Timeout));
+    }
+
+    #[test]
+    fn hash_is_kept_up_to_date_by_every_move() {
+        let mut game = Game::from_fen("r3k2r/1P4p1/8/3Pp3/8/8/8/R3K2R w KQkq e6 4 20").unwrap();
+        let start = game.hash();
+        for text in ["d5e6", "e8g8", "b7a8q", "g7g5", "e1c1"] {
+            game.play(text.parse().unwrap()).unwrap();
+            assert_eq!(game.hash(), game.full_hash(), "{}", text);
+            assert_eq!(game.hash(), Game::from_fen(&game.to_fen()).unwrap().hash(), "{}", text);
+        }
+        while game.undo_move().is_some() {}
+        assert_eq!(game.hash(), start);
+    }
+
+    #[test]
+    fn transpositions_have_the_same_hash() {
+        let mut first = Game::new();
+        let mut second = Game::new();
+        for text in ["g1f3", "g8f6", "b1c3"] {
+            first.play(text.parse().unwrap()).unwrap();
+        }
+        for text in ["b1c3", "g8f6", "g1f3"] {
+            second.play(text.parse().unwrap()).unwrap();
+        }
+
+        assert_eq!(first.hash(), second.hash());
+        assert_ne!(first.hash(), Game::new().hash());
+    }
+
+    #[test]
+    fn perft_counts_match_the_standard_positions() {
+        for (fen, counts) in [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8902][..]),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862][..]),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238][..]),
+            ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467][..]),
+            ("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1", &[6, 264, 9467][..]),
+            ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379][..]),
+            ("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[46, 2079, 89890][..]),
+        ] {
+            let game = Game::from_fen(fen).unwrap();
+            for (depth, &count) in counts.iter().enumerate() {
+                assert_eq!(game.perft(depth as u32 + 1), count, "{} at depth {}", fen, depth + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn perft_divide_adds_up_to_perft() {
+        let game = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let divide = game.perft_divide(2);
+
+        assert_eq!(divide.len(), 48);
+        assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), game.perft(2));
+        let castling = divide.iter().find(|(chess_move, _)| chess_move.to_string() == "e1g1").unwrap();
+        assert_eq!(castling.1, 43);
+        assert_eq!(Game::new().perft(0), 1);
+        assert!(Game::new().perft_divide(0).is_empty());
+    }
+
+    #[test]
+    fn san_moves_are_read_and_written() {
+        let mut game = Game::new();
+        for (text, san) in [("e2e4", "e4"), ("e7e5", "e5"), ("g1f3", "Nf3"), ("b8c6", "Nc6"), ("f1b5", "Bb5"), ("a7a6", "a6")] {
+            let chess_move: Move = text.parse().unwrap();
+            assert_eq!(game.move_to_san(&chess_move).unwrap(), san);
+            assert!(game.parse_san(san).unwrap().same_as(&chess_move));
+            game.play(chess_move).unwrap();
+        }
+        assert_eq!(game.move_to_san(&"b5c6".parse().unwrap()).unwrap(), "Bxc6");
+        assert_eq!(game.parse_san("Bxc6!?").unwrap().to_string(), "b5c6");
+        assert_eq!(game.move_to_san(&"e1g1".parse().unwrap()).unwrap(), "O-O");
+        assert_eq!(game.parse_san("O-O").unwrap().to_string(), "e1g1");
+        assert_eq!(game.parse_san("Kg1"), Err(ChessError::IllegalSan("Kg1".to_string())));
+    }
+
+    #[test]
+    fn san_tells_apart_pieces_going_to_the_same_square() {
+        use Color::*;
+        use Piece::*;
+        let game = setup(&[
+            ("e1", King, White),
+            ("b2", Knight, White),
+            ("f2", Knight, White),
+            ("a1", Rook, White),
+            ("a5", Rook, White),
+            ("h7", Queen, White),
+            ("h4", Queen, White),
+            ("e4", Queen, White),
+            ("b8", King, Black),
+        ]);
+
+        assert_eq!(game.move_to_san(&"b2d3".parse().unwrap()).unwrap(), "Nbd3");
+        assert_eq!(game.move_to_san(&"a1a3".parse().unwrap()).unwrap(), "R1a3");
+        assert_eq!(game.move_to_san(&"h4e1".parse().unwrap()).unwrap_err(), ChessError::IllegalMove("h4e1".parse().unwrap()));
+        assert_eq!(game.move_to_san(&"h4e7".parse().unwrap()).unwrap(), "Qh4e7");
+        assert_eq!(game.parse_san("Nbd3").unwrap().to_string(), "b2d3");
+        assert_eq!(game.parse_san("R5a3").unwrap().to_string(), "a5a3");
+        assert_eq!(game.parse_san("Qh4e7").unwrap().to_string(), "h4e7");
+        assert_eq!(game.parse_san("Nd3"), Err(ChessError::AmbiguousSan("Nd3".to_string())));
+        assert_eq!(game.parse_san("Qe7"), Err(ChessError::AmbiguousSan("Qe7".to_string())));
+        assert_eq!(game.parse_san("Nd4"), Err(ChessError::IllegalSan("Nd4".to_string())));
+        for text in ["", "Pe4", "Nz3", "e8=K", "xe4", "Nbd3d", "O-O-O-O"] {
+            assert_eq!(game.parse_san(text), Err(ChessError::InvalidSan(text.to_string())), "{}", text);
+        }
+    }
+
+    #[test]
+    fn san_marks_promotions_checks_and_mates() {
+        use Color::*;
+        use Piece::*;
+        let game = setup(&[("g1", King, White), ("b7", Pawn, White), ("a2", Rook, White), ("c8", Knight, Black), ("h8", King, Black)]);
+
+        assert_eq!(game.move_to_san(&"b7c8q".parse().unwrap()).unwrap(), "bxc8=Q+");
+        assert_eq!(game.move_to_san(&"b7b8n".parse().unwrap()).unwrap(), "b8=N");
+        assert_eq!(game.move_to_san(&"a2a8".parse().unwrap()).unwrap(), "Ra8");
+        assert_eq!(game.move_to_san(&"a2h2".parse().unwrap()).unwrap(), "Rh2+");
+        assert_eq!(game.parse_san("bxc8=Q+").unwrap().to_string(), "b7c8q");
+        assert_eq!(game.parse_san("bxc8R").unwrap().to_string(), "b7c8r");
+        assert_eq!(game.parse_san("b8"), Err(ChessError::IllegalSan("b8".to_string())));
+
+        let mate = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert_eq!(mate.move_to_san(&"a1a8".parse().unwrap()).unwrap(), "Ra8#");
+        assert_eq!(mate.parse_san("Ra8#").unwrap().to_string(), "a1a8");
+    }
+
+    #[test]
+    fn uci_moves_are_checked_against_the_legal_moves() {
+        let mut game = Game::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+
+        let en_passant = game.parse_uci("e5d6").unwrap();
+        assert!(en_passant.flags.en_passant && en_passant.flags.capture);
+        assert!(game.parse_uci("e1g1").unwrap().flags.castling);
+        assert_eq!(game.parse_uci("b7a8q").unwrap().promotion, Some(Piece::Queen));
+        assert_eq!(game.move_to_uci(&"e1c1".parse().unwrap()).unwrap(), "e1c1");
+        assert_eq!(game.parse_uci("b7b8"), Err(ChessError::MissingPromotion("b7b8".parse().unwrap())));
+        assert_eq!(game.parse_uci("e1e3"), Err(ChessError::IllegalMove("e1e3".parse().unwrap())));
+        assert_eq!(game.parse_uci("e8e7"), Err(ChessError::WrongSide(square("e8"))));
+        assert_eq!(game.parse_uci("c3c4"), Err(ChessError::EmptySquare(square("c3"))));
+        for text in ["", "e2", "e2e4 ", "E2E4", "b7a8Q", "b7a8qq", "e1-g1"] {
+            assert_eq!(game.parse_uci(text), Err(ChessError::InvalidMove(text.to_string())), "{}", text);
+        }
+
+        assert_eq!(game.play_uci("e1g1"), Ok(GameState::InProgress));
+        assert_eq!(game.to_fen(), "r3k2r/1P6/8/3pP3/8/8/8/R4RK1 b kq - 1 1");
+    }
+
+    #[cfg(test)]
+    const TWO_GAMES: &str = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+[Annotator "Someone \"quoted\""]
+
+{Opening comment} 1. e4 e5 2. Nf3 Nc6 3. Bb5 {This opening is called the Ruy Lopez.}
+3... a6 $1 4. Ba4 (4. Bxc6 dxc6 (4... bxc6?! 5. O-O) 5. O-O) 4... Nf6!? ; a line comment
+5. O-O Be7 1/2-1/2
+
+% an escaped line that is skipped
+[Event "Second"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+[SetUp "1"]
+
+1. e4 Kd7 *
+"#;
+
+    #[test]
+    fn pgn_games_are_read_with_tags_comments_and_variations() {
+        let games: Vec<PgnGame> = PgnReader::new(TWO_GAMES.as_bytes()).map(Result::unwrap).collect();
+        assert_eq!(games.len(), 2);
+
+        let first = &games[0];
+        assert_eq!(first.tags.len(), 8);
+        for name in SEVEN_TAG_ROSTER {
+            assert!(first.tag(name).is_some(), "{}", name);
+        }
+        assert_eq!(first.tag("White"), Some("Fischer, Robert J."));
+        assert_eq!(first.tag("Annotator"), Some("Someone \"quoted\""));
+        assert_eq!(first.result, "1/2-1/2");
+        let sans: Vec<&str> = first.moves.iter().map(|pgn_move| pgn_move.san.as_str()).collect();
+        assert_eq!(sans, ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4", "Nf6", "O-O", "Be7"]);
+        assert_eq!(first.moves[0].comments_before, ["Opening comment"]);
+        assert_eq!(first.moves[4].comments, ["This opening is called the Ruy Lopez."]);
+        assert_eq!(first.moves[5].nags, [1]);
+        assert_eq!(first.moves[7].nags, [5]);
+        assert_eq!(first.moves[7].comments, ["a line comment"]);
+
+        let variation = &first.moves[6].variations[0];
+        assert_eq!(variation.len(), 3);
+        assert_eq!(variation[0].chess_move.to_string(), "b5c6");
+        assert_eq!(variation[1].variations[0][0].san, "bxc6");
+        assert_eq!(variation[1].variations[0][0].nags, [6]);
+        assert_eq!(variation[1].variations[0][1].san, "O-O");
+        assert_eq!(first.game.to_fen(), "r1bqk2r/1pppbppp/p1n2n2/4p3/B3P3/5N2/PPPP1PPP/RNBQ1RK1 w kq - 4 6");
+        assert_eq!(first.game.history().len(), 10);
+
+        let second = &games[1];
+        assert_eq!(second.result, "*");
+        assert_eq!(second.game.to_fen(), "8/3k4/8/8/4P3/8/8/4K3 w - - 1 2");
+        assert_eq!(Game::from_pgn(TWO_GAMES).unwrap(), first.game);
+    }
+
+    #[test]
+    fn pgn_errors_tell_the_line_and_the_move() {
+        let pgn = "[Event \"?\"]\n\n1. e4 e5\n2. Nf3 Nf6 3. Nxe5 Nxe5 *\n\n[Event \"next\"]\n\n1. d4 *\n";
+        let mut reader = PgnReader::new(pgn.as_bytes());
+        assert_eq!(
+            reader.next(),
+            Some(Err(ChessError::InvalidPgn {
+                line: 4,
+                reason: "3... Nxe5: Nxe5 is not a legal move".to_string()
+            }))
+        );
+        assert_eq!(reader.next().unwrap().unwrap().moves[0].san, "d4");
+        assert!(reader.next().is_none());
+
+        let mut reader = PgnReader::new("1. e4 e5 1-0\n\n1. d4 d5 0-1\n".as_bytes());
+        assert_eq!(reader.next().unwrap().unwrap().result, "1-0");
+        assert_eq!(reader.next().unwrap().unwrap().result, "0-1");
+        assert!(reader.next().is_none());
+
+        let mut reader = PgnReader::new("1. e4 {1-0} (1. d4 d5) 1... e5 * 1. c4 *".as_bytes());
+        assert_eq!(reader.next().unwrap().unwrap().moves.len(), 2);
+        assert_eq!(reader.next().unwrap().unwrap().moves[0].san, "c4");
+        assert!(reader.next().is_none());
+
+        for (pgn, line) in [
+            ("1. e4 e5", 1),
+            ("1. e4 {never closed\n\n*", 1),
+            ("[Event \"?\"\n1. e4 *", 1),
+            ("1. e4 (1... e5 *", 1),
+            ("1. e4\ne5 ) *", 2),
+            ("(1. e4) *", 1),
+            ("1. e4 e5 2. Ke3 *", 1),
+            ("[Event \"x\"]\n[FEN \"bad\"]\n\n1. e4 *", 2),
+        ] {
+            assert!(
+                matches!(Game::from_pgn(pgn), Err(ChessError::InvalidPgn { line: found, .. }) if found == line),
+                "{:?} gives {:?}",
+                pgn,
+                Game::from_pgn(pgn)
+            );
+        }
+    }
+
+    #[test]
+    fn played_games_are_written_as_pgn() {
+        let mut game = Game::new();
+        for san in ["f3", "e5", "g4", "Qh4#"] {
+            let chess_move = game.parse_san(san).unwrap();
+            game.play(chess_move).unwrap();
+        }
+
+        assert_eq!(
+            game.to_pgn(),
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"?\"]\n[Black \"?\"]\n\
+             [Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n"
+        );
+
+        let pgn = PgnWriter::new()
+            .tag("White", "Fool")
+            .tag("Black", "Scholar \"the\" Quick")
+            .tag("Result", "1-0")
+            .tag("Annotator", "Nobody")
+            .tag("White", "Fool, A.")
+            .comment(1, "Already a mistake")
+            .clock(1, std::time::Duration::from_secs(3725))
+            .write(&game);
+        assert!(pgn.contains("[White \"Fool, A.\"]\n[Black \"Scholar \\\"the\\\" Quick\"]\n[Result \"0-1\"]\n[Annotator \"Nobody\"]\n"));
+        assert!(pgn.ends_with("\n\n1. f3 e5 {[%clk 1:02:05] Already a mistake} 2. g4 Qh4# 0-1\n"));
+
+        let read = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(read.tag("Black"), Some("Scholar \"the\" Quick"));
+        assert_eq!(read.moves[1].comments, ["[%clk 1:02:05] Already a mistake"]);
+        assert_eq!(read.game.to_fen(), game.to_fen());
+        assert_eq!(read.game.outcome(), game.outcome());
+
+        let mut game = Game::new();
+        for san in ["e4", "e5", "Qh5", "Ke7"] {
+            let chess_move = game.parse_san(san).unwrap();
+            game.play(chess_move).unwrap();
+        }
+        let mut timed_out = game.clone();
+        game.resign(Color::Black).unwrap();
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[Result \"1-0\"]\n[Termination \"normal\"]\n\n1. e4 e5 2. Qh5 Ke7 1-0\n"));
+        assert_eq!(Game::from_pgn(&pgn).unwrap().outcome(), Outcome::WhiteWins(Reason::Resignation));
+        timed_out.time_out(Color::White).unwrap();
+        let pgn = timed_out.to_pgn();
+        assert!(pgn.contains("[Termination \"time forfeit\"]"));
+        assert_eq!(Game::from_pgn(&pgn).unwrap().outcome(), Outcome::BlackWins(Reason::Timeout));
+
+        let drawn = Game::from_pgn("1. e4 e5 1/2-1/2").unwrap();
+        assert_eq!(drawn.outcome(), Outcome::Draw(Reason::Unknown));
+        let drawn = Game::from_pgn("[Termination \"normal\"]\n\n1. e4 e5 1/2-1/2").unwrap();
+        assert_eq!(drawn.outcome(), Outcome::Draw(Reason::Agreement));
+        assert!(matches!(
+            Game::from_pgn("1. f3 e5 2. g4 Qh4# 1-0"),
+            Err(ChessError::InvalidPgn { line: 1, .. })
+        ));
+
+        let pgn = PgnWriter::new().tag("FEN", "junk").tag("SetUp", "1").write(&Game::new());
+        assert!(!pgn.contains("FEN") && !pgn.contains("SetUp"));
+        assert!(Game::from_pgn(&pgn).is_ok());
+    }
+
+    #[test]
+    fn long_pgn_is_wrapped_and_custom_starts_keep_their_fen() {
+        let mut game = Game::from_fen("4k1n1/8/8/8/8/8/8/R3K1N1 b Q - 0 30").unwrap();
+        for text in ["e8d8", "a1a2", "d8e8", "a2a1", "e8d8"] {
+            game.play(text.parse().unwrap()).unwrap();
+        }
+        for _ in 0..3 {
+            shuffle_knights(&mut game);
+        }
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k1n1/8/8/8/8/8/8/R3K1N1 b Q - 0 30\"]\n\n30... Kd8 31. Ra2"));
+        let movetext = pgn.split("\n\n").nth(1).unwrap();
+        assert!(movetext.lines().count() > 1);
+        assert!(movetext.lines().all(|line| line.len() <= 80));
+
+        let read = Game::from_pgn(&pgn).unwrap();
+        assert_eq!(read.history(), game.history());
+        assert_eq!(read.to_fen(), game.to_fen());
+    }
+
+    #[test]
+    fn board_is_displayed_from_either_side() {
+        let mut game = Game::new();
+        game.play("e2e4".parse().unwrap()).unwrap();
+
+        assert_eq!(
+            game.to_string(),
+            "8 r n b q k b n r\n7 p p p p p p p p\n6 . . . . . . . .\n5 . . . . . . . .\n\
+             4 . . . . P . . .\n3 . . . . . . . .\n2 P P P P . P P P\n1 R N B Q K B N R\n  a b c d e f g h"
+        );
+        assert_eq!(
+            format!("{:#}", game),
+            "1 R N B K Q B N R\n2 P P P . P P P P\n3 . . . . . . . .\n4 . . . P . . . .\n\
+             5 . . . . . . . .\n6 . . . . . . . .\n7 p p p p p p p p\n8 r n b k q b n r\n  h g f e d c b a"
+        );
+        assert_eq!(format!("{:#}", game), game.diagram(Color::Black));
+        assert_eq!(
+            format!("{:?}", game),
+            format!(
+                "{}\nSide to move: Black\nCastling: KQkq\nFEN: rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+                game
+            )
+        );
+        assert!(format!("{:?}", Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap()).contains("Castling: -\n"));
+    }
+
+    #[test]
+    fn plain_rendering_marks_the_last_move_and_the_legal_targets() {
+        let mut game = Game::new();
+        game.play("e2e4".parse().unwrap()).unwrap();
+
+        let board = game.render(&RenderOptions::default());
+        let lines: Vec<&str> = board.lines().collect();
+        assert_eq!(lines.len(), 9);
+        assert_eq!(lines[0], "8  r  n  b  q  k  b  n  r ");
+        assert_eq!(lines[4], "4  .  .  .  . [P] .  .  . ");
+        assert_eq!(lines[6], "2  P  P  P  P [.] P  P  P ");
+        assert_eq!(lines[8], "   a  b  c  d  e  f  g  h ");
+
+        let options = RenderOptions {
+            orientation: Color::Black,
+            coordinates: false,
+            last_move: false,
+            selected: Some(square("g8")),
+            ..RenderOptions::ascii()
+        };
+        let board = game.render(&options);
+        let lines: Vec<&str> = board.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[0], " R  N  B  K  Q  B  N  R ");
+        assert_eq!(lines[5], "(.) . (.) .  .  .  .  . ");
+        assert_eq!(lines[7], " r [n] b  k  q  b  n  r ");
+    }
+
+    #[test]
+    fn unicode_rendering_colors_the_squares() {
+        let mut game = Game::new();
+        game.play("g1f3".parse().unwrap()).unwrap();
+
+        let board = game.render(&RenderOptions::unicode());
+        let lines: Vec<&str> = board.lines().collect();
+        assert!(lines[0].starts_with("8 \x1b[48;5;180;38;5;16m ♜ \x1b[0m\x1b[48;5;137;38;5;16m ♞ \x1b[0m"));
+        assert_eq!(lines[7].matches("\x1b[0m").count(), 8);
+        assert!(lines[7].contains("\x1b[48;5;143;38;5;231m   \x1b[0m"));
+        assert!(lines[5].contains("\x1b[48;5;143;38;5;231m ♘ \x1b[0m"));
+        assert!(board.contains('♔') && board.contains('♛'));
+
+        let plain_glyphs = game.render(&RenderOptions {
+            ansi_colors: false,
+            ..RenderOptions::unicode()
+        });
+        assert!(!plain_glyphs.contains('\x1b'));
+        assert_eq!(plain_glyphs.lines().nth(5).unwrap(), "3  .  .  .  .  . [♘] .  . ");
+    }
+
+    #[test]
+    fn svg_diagrams_are_self_contained() {
+        let mut game = Game::new();
+        for text in ["e2e4", "f7f6", "d1h5"] {
+            game.play(text.parse().unwrap()).unwrap();
+        }
+
+        let svg = game.to_svg(&SvgOptions::default());
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 360 360\" width=\"360\" height=\"360\">\n"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(!svg.contains("href") && !svg.contains("url("));
+        assert_eq!(svg.matches("<g transform=").count(), 32);
+        assert_eq!(svg.matches("fill-opacity=\"0.4\"").count(), 2);
+        assert!(svg.contains("<circle cx=\"202.5\" cy=\"22.5\" r=\"22.5\" fill=\"#e0301e\" fill-opacity=\"0.7\"/>"));
+        assert_eq!(svg.matches("</text>").count(), 16);
+        assert!(svg.contains(">a</text>") && svg.contains(">8</text>"));
+
+        let options = SvgOptions {
+            size: 200,
+            orientation: Color::Black,
+            coordinates: false,
+            last_move: false,
+            check: false,
+            highlights: vec![square("a1")],
+            arrows: vec![(square("e2"), square("e4"))],
+        };
+        let svg = game.to_svg(&options);
+        assert!(svg.contains("width=\"200\" height=\"200\""));
+        assert!(!svg.contains("</text>") && !svg.contains("#e0301e"));
+        assert!(svg.contains("<rect x=\"315\" y=\"0\" width=\"45\" height=\"45\" fill=\"#9bc700\" fill-opacity=\"0.4\"/>"));
+        assert!(svg.contains("<line x1=\"157.5\" y1=\"67.5\" x2=\"157.5\" y2=\"139.5\""));
+        assert!(svg.contains("<polygon points=\"157.5,157.5 146.5,139.5 168.5,139.5\""));
+    }
@@ -0,0 +1,195 @@
+//! The board as a set of 64 bit masks, one bit per square with a1 as the lowest bit.
+//! There is a mask for every kind of piece and one for every color, a piece
+//! is where the mask of its kind and the mask of its color overlap.
+//! Knight, king and pawn attacks are looked up in tables built at compile time,
+//! sliding pieces follow precomputed rays up to the first blocker
+
+use super::color::Color;
+use super::piece::Piece;
+use super::square::Square;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Board {
+    pieces: [u64; 6],
+    colors: [u64; 2],
+}
+
+/// (rank, file) steps of the eight ray directions, the first four going
+/// towards higher squares and the last four towards lower ones
+const DIRECTIONS: [(i8, i8); 8] = [(1, 0), (0, 1), (1, 1), (1, -1), (-1, 0), (0, -1), (-1, -1), (-1, 1)];
+const KNIGHT_JUMPS: [(i8, i8); 8] = [(1, 2), (1, -2), (-1, 2), (-1, -2), (2, 1), (2, -1), (-2, 1), (-2, -1)];
+const KING_STEPS: [(i8, i8); 8] = [(1, 1), (1, 0), (1, -1), (0, 1), (0, -1), (-1, 1), (-1, 0), (-1, -1)];
+
+static KNIGHT_ATTACKS: [u64; 64] = step_table(&KNIGHT_JUMPS);
+static KING_ATTACKS: [u64; 64] = step_table(&KING_STEPS);
+/// Squares a pawn of each color attacks, White first
+static PAWN_ATTACKS: [[u64; 64]; 2] = [step_table(&[(1, 1), (1, -1)]), step_table(&[(-1, 1), (-1, -1)])];
+/// Every square from a square to the edge of the board in each direction
+static RAYS: [[u64; 64]; 8] = ray_table();
+
+/// The bit of the square
+pub fn bit(square: Square) -> u64 {
+    1 << square.index()
+}
+
+/// The squares of the set bits, lowest first
+pub fn squares(mut mask: u64) -> impl Iterator<Item = Square> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let index = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Square::from_index(index)
+    })
+}
+
+const fn offset(index: usize, rank: i8, file: i8) -> Option<usize> {
+    let rank = (index / 8) as i8 + rank;
+    let file = (index % 8) as i8 + file;
+    if rank < 0 || rank > 7 || file < 0 || file > 7 {
+        None
+    } else {
+        Some(rank as usize * 8 + file as usize)
+    }
+}
+
+const fn step_table(steps: &[(i8, i8)]) -> [u64; 64] {
+    let mut table = [0; 64];
+    let mut index = 0;
+    while index < 64 {
+        let mut step = 0;
+        while step < steps.len() {
+            if let Some(to) = offset(index, steps[step].0, steps[step].1) {
+                table[index] |= 1 << to;
+            }
+            step += 1;
+        }
+        index += 1;
+    }
+    table
+}
+
+const fn ray_table() -> [[u64; 64]; 8] {
+    let mut table = [[0; 64]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let (rank, file) = DIRECTIONS[direction];
+        let mut index = 0;
+        while index < 64 {
+            let mut next = offset(index, rank, file);
+            while let Some(to) = next {
+                table[direction][index] |= 1 << to;
+                next = offset(to, rank, file);
+            }
+            index += 1;
+        }
+        direction += 1;
+    }
+    table
+}
+
+/// The ray from the square in the direction, cut off after the first occupied square
+fn ray_attacks(square: Square, direction: usize, occupied: u64) -> u64 {
+    let ray = RAYS[direction][square.index()];
+    let blockers = ray & occupied;
+    if blockers == 0 {
+        return ray;
+    }
+    let first = if direction < 4 {
+        blockers.trailing_zeros() as usize
+    } else {
+        63 - blockers.leading_zeros() as usize
+    };
+    ray ^ RAYS[direction][first]
+}
+
+pub fn knight_attacks(square: Square) -> u64 {
+    KNIGHT_ATTACKS[square.index()]
+}
+
+pub fn king_attacks(square: Square) -> u64 {
+    KING_ATTACKS[square.index()]
+}
+
+/// The two diagonal squares in front of a pawn of the color
+pub fn pawn_attacks(square: Square, color: Color) -> u64 {
+    PAWN_ATTACKS[color as usize][square.index()]
+}
+
+pub fn rook_attacks(square: Square, occupied: u64) -> u64 {
+    [0, 1, 4, 5]
+        .iter()
+        .fold(0, |attacks, &direction| attacks | ray_attacks(square, direction, occupied))
+}
+
+pub fn bishop_attacks(square: Square, occupied: u64) -> u64 {
+    [2, 3, 6, 7]
+        .iter()
+        .fold(0, |attacks, &direction| attacks | ray_attacks(square, direction, occupied))
+}
+
+impl Board {
+    pub fn empty() -> Self {
+        Self {
+            pieces: [0; 6],
+            colors: [0; 2],
+        }
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<(Piece, Color)> {
+        let bit = bit(square);
+        let color = if self.colors[0] & bit != 0 {
+            Color::White
+        } else if self.colors[1] & bit != 0 {
+            Color::Black
+        } else {
+            return None;
+        };
+        PIECES
+            .iter()
+            .find(|&&piece| self.pieces[piece as usize] & bit != 0)
+            .map(|&piece| (piece, color))
+    }
+
+    pub fn set(&mut self, square: Square, piece: Option<(Piece, Color)>) {
+        let bit = bit(square);
+        for mask in self.pieces.iter_mut().chain(self.colors.iter_mut()) {
+            *mask &= !bit;
+        }
+        if let Some((piece, color)) = piece {
+            self.pieces[piece as usize] |= bit;
+            self.colors[color as usize] |= bit;
+        }
+    }
+
+    /// Every square with a piece on it
+    pub fn occupied(&self) -> u64 {
+        self.colors[0] | self.colors[1]
+    }
+
+    /// Every square with a piece of the color
+    pub fn color(&self, color: Color) -> u64 {
+        self.colors[color as usize]
+    }
+
+    /// Every square with the given piece of the color
+    pub fn pieces(&self, piece: Piece, color: Color) -> u64 {
+        self.pieces[piece as usize] & self.colors[color as usize]
+    }
+
+    /// Every piece of the given color attacking the square
+    pub fn attackers(&self, square: Square, by: Color) -> u64 {
+        use Piece::*;
+        let occupied = self.occupied();
+        let diagonal = self.pieces(Bishop, by) | self.pieces(Queen, by);
+        let straight = self.pieces(Rook, by) | self.pieces(Queen, by);
+        (knight_attacks(square) & self.pieces(Knight, by))
+            | (king_attacks(square) & self.pieces(King, by))
+            | (pawn_attacks(square, by.opposite()) & self.pieces(Pawn, by))
+            | (bishop_attacks(square, occupied) & diagonal)
+            | (rook_attacks(square, occupied) & straight)
+    }
+}
+
+const PIECES: [Piece; 6] = [Piece::Pawn, Piece::Rook, Piece::Knight, Piece::Bishop, Piece::Queen, Piece::King];
@@ -0,0 +1,105 @@
+use std::fmt;
+
+use super::color::Color;
+use super::square::{File, Rank, Square};
+
+/// Which castlings each side is still allowed to make.
+/// A right is lost for good once the king or the rook in question has moved
+/// or the rook has been taken
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CastlingRights {
+    pub white_king_side: bool,
+    pub white_queen_side: bool,
+    pub black_king_side: bool,
+    pub black_queen_side: bool,
+}
+
+impl CastlingRights {
+    /// Both sides may castle both ways, as in the starting position
+    pub fn all() -> Self {
+        Self {
+            white_king_side: true,
+            white_queen_side: true,
+            black_king_side: true,
+            black_queen_side: true,
+        }
+    }
+
+    /// Neither side may castle
+    pub fn none() -> Self {
+        Self {
+            white_king_side: false,
+            white_queen_side: false,
+            black_king_side: false,
+            black_queen_side: false,
+        }
+    }
+
+    /// The four rights as bits, white king side being the lowest
+    pub fn bits(&self) -> u8 {
+        self.white_king_side as u8
+            | (self.white_queen_side as u8) << 1
+            | (self.black_king_side as u8) << 2
+            | (self.black_queen_side as u8) << 3
+    }
+
+    pub fn king_side(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_king_side,
+            Color::Black => self.black_king_side,
+        }
+    }
+
+    pub fn queen_side(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_queen_side,
+            Color::Black => self.black_queen_side,
+        }
+    }
+
+    /// The king has moved, so that side may not castle at all anymore
+    pub fn revoke(&mut self, color: Color) {
+        match color {
+            Color::White => {
+                self.white_king_side = false;
+                self.white_queen_side = false;
+            }
+            Color::Black => {
+                self.black_king_side = false;
+                self.black_queen_side = false;
+            }
+        }
+    }
+
+    /// Something moved from or to a corner, which means the rook
+    /// that started there is gone and cannot castle anymore
+    pub fn revoke_corner(&mut self, square: Square) {
+        match (square.file(), square.rank()) {
+            (File::A, Rank::First) => self.white_queen_side = false,
+            (File::H, Rank::First) => self.white_king_side = false,
+            (File::A, Rank::Eighth) => self.black_queen_side = false,
+            (File::H, Rank::Eighth) => self.black_king_side = false,
+            _ => {}
+        }
+    }
+}
+
+/// Written the way FEN does, "KQkq" for all four rights and "-" for none
+impl fmt::Display for CastlingRights {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.bits() == 0 {
+            return write!(f, "-");
+        }
+        for (right, letter) in [
+            (self.white_king_side, 'K'),
+            (self.white_queen_side, 'Q'),
+            (self.black_king_side, 'k'),
+            (self.black_queen_side, 'q'),
+        ] {
+            if right {
+                write!(f, "{}", letter)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::error::ChessError;
+use super::piece::Piece;
+use super::square::Square;
+
+/// A move of a piece from one square to another.
+/// The flags are filled in by the move generation, a move typed in
+/// by a player only needs the squares and the promotion piece
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<Piece>,
+    pub flags: MoveFlags,
+}
+
+/// What kind of move it is besides going from one square to another
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MoveFlags {
+    pub capture: bool,
+    pub double_push: bool,
+    pub en_passant: bool,
+    pub castling: bool,
+}
+
+impl Move {
+    pub fn new(from: Square, to: Square) -> Self {
+        Self {
+            from,
+            to,
+            promotion: None,
+            flags: MoveFlags::default(),
+        }
+    }
+
+    pub fn with_promotion(from: Square, to: Square, promotion: Piece) -> Self {
+        Self {
+            promotion: Some(promotion),
+            ..Self::new(from, to)
+        }
+    }
+
+    /// Whether two moves are the same move on the board, ignoring the flags
+    pub fn same_as(&self, other: &Move) -> bool {
+        self.from == other.from && self.to == other.to && self.promotion == other.promotion
+    }
+}
+
+/// Written as the two squares followed by the promotion piece, like e2e4 or e7e8q
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(piece) = self.promotion {
+            write!(f, "{}", piece.to_char())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Move {
+    type Err = ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ChessError::InvalidMove(s.to_string());
+        let from = s.get(0..2).ok_or_else(error)?.parse().map_err(|_| error())?;
+        let to = s.get(2..4).ok_or_else(error)?.parse().map_err(|_| error())?;
+        let mut chess_move = Move::new(from, to);
+        match s.get(4..).ok_or_else(error)? {
+            "" => {}
+            promotion if promotion.chars().count() == 1 => {
+                let piece = promotion.chars().next().and_then(Piece::from_char).ok_or_else(error)?;
+                chess_move.promotion = Some(piece);
+            }
+            _ => return Err(error()),
+        }
+        Ok(chess_move)
+    }
+}
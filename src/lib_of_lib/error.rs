@@ -0,0 +1,57 @@
+use std::fmt;
+
+use super::chess_move::Move;
+use super::square::Square;
+
+/// Everything that can go wrong when asking the game to do something
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChessError {
+    /// The text is not a square like "e4"
+    InvalidSquare(String),
+    /// The text is not a move like "e2e4" or "e7e8q"
+    InvalidMove(String),
+    /// There is no piece on the square to move
+    EmptySquare(Square),
+    /// The piece on the square belongs to the side that is not to move
+    WrongSide(Square),
+    /// The move breaks the rules, for example by leaving the king in check
+    IllegalMove(Move),
+    /// No more moves can be made since the game has ended
+    GameOver,
+    /// A pawn reaching the last rank has to be told what to become
+    MissingPromotion(Move),
+    /// A draw was claimed in a position where the rules do not allow it
+    NoDrawToClaim,
+    /// The FEN text does not describe a position, with the reason why
+    InvalidFen(String),
+    /// The text is not written in Standard Algebraic Notation like "Nbd7" or "exd8=Q+"
+    InvalidSan(String),
+    /// The SAN text does not match any legal move
+    IllegalSan(String),
+    /// The SAN text matches more than one legal move and needs the square the piece comes from
+    AmbiguousSan(String),
+    /// The PGN text could not be read, with the line and the move where it went wrong
+    InvalidPgn { line: usize, reason: String },
+}
+
+impl fmt::Display for ChessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChessError::InvalidSquare(text) => write!(f, "\"{}\" is not a square", text),
+            ChessError::InvalidMove(text) => write!(f, "\"{}\" is not a move", text),
+            ChessError::EmptySquare(square) => write!(f, "there is no piece on {}", square),
+            ChessError::WrongSide(square) => write!(f, "the piece on {} belongs to the side not to move", square),
+            ChessError::IllegalMove(chess_move) => write!(f, "{} is not a legal move", chess_move),
+            ChessError::GameOver => write!(f, "the game is already over"),
+            ChessError::MissingPromotion(chess_move) => write!(f, "{} needs a piece to promote to", chess_move),
+            ChessError::NoDrawToClaim => write!(f, "there is no draw to claim"),
+            ChessError::InvalidFen(reason) => write!(f, "invalid FEN: {}", reason),
+            ChessError::InvalidSan(text) => write!(f, "\"{}\" is not a move in algebraic notation", text),
+            ChessError::IllegalSan(text) => write!(f, "{} is not a legal move", text),
+            ChessError::AmbiguousSan(text) => write!(f, "{} fits more than one legal move", text),
+            ChessError::InvalidPgn { line, reason } => write!(f, "invalid PGN on line {}: {}", line, reason),
+        }
+    }
+}
+
+impl std::error::Error for ChessError {}
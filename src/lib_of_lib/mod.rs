@@ -1,2 +1,8 @@
 pub mod piece;
 pub mod color;
+pub mod castling;
+pub mod square;
+pub mod chess_move;
+pub mod error;
+pub mod zobrist;
+pub mod bitboard;
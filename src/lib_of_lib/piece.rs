@@ -1,4 +1,4 @@
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Piece {
     Pawn,
     Rook,
@@ -6,4 +6,31 @@ pub enum Piece {
     Bishop,
     Queen,
     King,
-}
\ No newline at end of file
+}
+
+impl Piece {
+    /// The lowercase letter of the piece as used in move notation, p for pawn
+    pub fn to_char(self) -> char {
+        match self {
+            Piece::Pawn => 'p',
+            Piece::Rook => 'r',
+            Piece::Knight => 'n',
+            Piece::Bishop => 'b',
+            Piece::Queen => 'q',
+            Piece::King => 'k',
+        }
+    }
+
+    /// Reads a piece letter in either case
+    pub fn from_char(c: char) -> Option<Piece> {
+        match c.to_ascii_lowercase() {
+            'p' => Some(Piece::Pawn),
+            'r' => Some(Piece::Rook),
+            'n' => Some(Piece::Knight),
+            'b' => Some(Piece::Bishop),
+            'q' => Some(Piece::Queen),
+            'k' => Some(Piece::King),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::error::ChessError;
+
+/// The columns of the board, a to h
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum File {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+}
+
+/// The rows of the board, counted from White's side
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rank {
+    First,
+    Second,
+    Third,
+    Fourth,
+    Fifth,
+    Sixth,
+    Seventh,
+    Eighth,
+}
+
+/// One of the 64 squares, numbered from a1 = 0, b1 = 1 up to h8 = 63
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Square(u8);
+
+impl File {
+    pub const ALL: [File; 8] = [File::A, File::B, File::C, File::D, File::E, File::F, File::G, File::H];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn from_index(index: usize) -> Option<File> {
+        File::ALL.get(index).copied()
+    }
+
+    pub fn to_char(self) -> char {
+        (b'a' + self as u8) as char
+    }
+
+    pub fn from_char(c: char) -> Option<File> {
+        File::ALL.into_iter().find(|file| file.to_char() == c)
+    }
+}
+
+impl Rank {
+    pub const ALL: [Rank; 8] = [
+        Rank::First,
+        Rank::Second,
+        Rank::Third,
+        Rank::Fourth,
+        Rank::Fifth,
+        Rank::Sixth,
+        Rank::Seventh,
+        Rank::Eighth,
+    ];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn from_index(index: usize) -> Option<Rank> {
+        Rank::ALL.get(index).copied()
+    }
+
+    pub fn to_char(self) -> char {
+        (b'1' + self as u8) as char
+    }
+
+    pub fn from_char(c: char) -> Option<Rank> {
+        Rank::ALL.into_iter().find(|rank| rank.to_char() == c)
+    }
+}
+
+impl Square {
+    pub fn new(file: File, rank: Rank) -> Self {
+        Square((rank as u8) * 8 + file as u8)
+    }
+
+    /// The square with the given number, None outside of 0..64
+    pub fn from_index(index: usize) -> Option<Square> {
+        if index < 64 {
+            Some(Square(index as u8))
+        } else {
+            None
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn file(self) -> File {
+        File::ALL[self.index() % 8]
+    }
+
+    pub fn rank(self) -> Rank {
+        Rank::ALL[self.index() / 8]
+    }
+
+    /// Whether the square is one of the dark squares, like a1 and h8
+    pub fn is_dark(self) -> bool {
+        (self.file().index() + self.rank().index()) % 2 == 0
+    }
+
+    /// The square the given number of ranks and files away,
+    /// None if that falls off the board
+    pub fn offset(self, rank: i8, file: i8) -> Option<Square> {
+        let file = File::from_index(usize::try_from(self.file() as i8 + file).ok()?)?;
+        let rank = Rank::from_index(usize::try_from(self.rank() as i8 + rank).ok()?)?;
+        Some(Square::new(file, rank))
+    }
+
+    /// Every square from a1 to h8
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).map(Square)
+    }
+}
+
+impl fmt::Display for File {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.file(), self.rank())
+    }
+}
+
+impl FromStr for Square {
+    type Err = ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next().and_then(File::from_char), chars.next().and_then(Rank::from_char), chars.next()) {
+            (Some(file), Some(rank), None) => Ok(Square::new(file, rank)),
+            _ => Err(ChessError::InvalidSquare(s.to_string())),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Random numbers for Zobrist hashing. A position is hashed by xoring together
+//! the number of every piece on its square, the side to move, the castling rights
+//! and the en passant file, so a move only has to xor in and out what it changed.
+//! The numbers are made by a fixed generator so hashes stay the same between runs
+
+use super::color::Color;
+use super::piece::Piece;
+use super::square::Square;
+
+pub struct Keys {
+    pieces: [[u64; 64]; 12],
+    black_to_move: u64,
+    castling: [u64; 16],
+    en_passant: [u64; 8],
+}
+
+pub static KEYS: Keys = Keys::new();
+
+/// splitmix64, small and good enough to fill the tables at compile time
+const fn next(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (state, z ^ (z >> 31))
+}
+
+impl Keys {
+    const fn new() -> Self {
+        let mut state = 0x1234_5678_9ABC_DEF0;
+        let mut pieces = [[0; 64]; 12];
+        let mut piece = 0;
+        while piece < 12 {
+            let mut square = 0;
+            while square < 64 {
+                let (new_state, key) = next(state);
+                state = new_state;
+                pieces[piece][square] = key;
+                square += 1;
+            }
+            piece += 1;
+        }
+        let (new_state, black_to_move) = next(state);
+        state = new_state;
+        let mut castling = [0; 16];
+        let mut rights = 0;
+        while rights < 16 {
+            let (new_state, key) = next(state);
+            state = new_state;
+            castling[rights] = key;
+            rights += 1;
+        }
+        let mut en_passant = [0; 8];
+        let mut file = 0;
+        while file < 8 {
+            let (new_state, key) = next(state);
+            state = new_state;
+            en_passant[file] = key;
+            file += 1;
+        }
+        Self {
+            pieces,
+            black_to_move,
+            castling,
+            en_passant,
+        }
+    }
+
+    pub fn piece(&self, piece: Piece, color: Color, square: Square) -> u64 {
+        let index = piece as usize + if color == Color::Black { 6 } else { 0 };
+        self.pieces[index][square.index()]
+    }
+
+    pub fn side(&self, color: Color) -> u64 {
+        match color {
+            Color::White => 0,
+            Color::Black => self.black_to_move,
+        }
+    }
+
+    /// Takes the castling rights as four bits, see `CastlingRights::bits`
+    pub fn castling(&self, rights: u8) -> u64 {
+        self.castling[rights as usize & 15]
+    }
+
+    pub fn en_passant(&self, square: Square) -> u64 {
+        self.en_passant[square.file().index()]
+    }
+}
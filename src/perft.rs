@@ -0,0 +1,47 @@
+use crate::{Game, Move};
+
+/// Perft, short for performance test, walks the tree of legal moves to a fixed depth
+/// and counts the positions at the bottom. The counts for well known positions are
+/// published, so comparing against them shows whether the move generation is right
+impl Game {
+    /// The number of positions reached after exactly `depth` more moves.
+    /// Only the position matters, the game history and a finished game are ignored
+    pub fn perft(&self, depth: u32) -> u64 {
+        self.position_only().count_nodes(depth)
+    }
+
+    /// Perft split up by the first move, which narrows down the move
+    /// a wrong count comes from when compared with another engine
+    pub fn perft_divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+        let mut game = self.position_only();
+        game.legal_moves()
+            .into_iter()
+            .map(|chess_move| {
+                let undo = game.apply_move(chess_move);
+                let nodes = game.count_nodes(depth - 1);
+                game.unapply_move(undo);
+                (chess_move, nodes)
+            })
+            .collect()
+    }
+
+    fn count_nodes(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        let mut nodes = 0;
+        for chess_move in moves {
+            let undo = self.apply_move(chess_move);
+            nodes += self.count_nodes(depth - 1);
+            self.unapply_move(undo);
+        }
+        nodes
+    }
+}
@@ -0,0 +1,588 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Lines};
+use std::time::Duration;
+
+use crate::{ChessError, Color, Game, GameState, Move, Outcome, Reason};
+
+/// The tags every PGN game is expected to have, in the order they are written
+pub const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+/// Lines of movetext are kept below this length
+const LINE_WIDTH: usize = 80;
+
+/// A game read from Portable Game Notation, with everything written around the moves
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnGame {
+    /// The tag pairs in the order they appear, like ("White", "Carlsen, Magnus")
+    pub tags: Vec<(String, String)>,
+    /// The main line of the game
+    pub moves: Vec<PgnMove>,
+    /// "1-0", "0-1", "1/2-1/2" or "*" for a game that is not finished
+    pub result: String,
+    /// The game after all moves of the main line have been played, ended
+    /// by the result when the board alone does not end it
+    pub game: Game,
+}
+
+/// A move of the movetext together with its annotations
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnMove {
+    /// The move as it was written, without "!" and "?" annotations
+    pub san: String,
+    pub chess_move: Move,
+    /// Numeric annotation glyphs like $1 for a good move, "!" and "?" are turned into these
+    pub nags: Vec<u8>,
+    /// Comments written before the move, which only happens at the start of a line
+    pub comments_before: Vec<String>,
+    /// Comments written after the move
+    pub comments: Vec<String>,
+    /// Other moves that could have been played instead of this one, each with its continuation
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+impl PgnGame {
+    /// The value of the tag with the given name
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl Game {
+    /// Reads the first game of the PGN text and plays its main line
+    pub fn from_pgn(pgn: &str) -> Result<Game, ChessError> {
+        match PgnReader::new(pgn.as_bytes()).next() {
+            Some(game) => game.map(|game| game.game),
+            None => Err(invalid(1, "there is no game".to_string())),
+        }
+    }
+
+    /// The moves played so far as PGN, with unknown tags written as "?".
+    /// Use a `PgnWriter` to fill in the tags or add comments
+    pub fn to_pgn(&self) -> String {
+        PgnWriter::new().write(self)
+    }
+}
+
+/// Writes games as PGN in the export format: the seven tag roster first, then
+/// FEN and SetUp for games that did not start from the usual position,
+/// then any other tags and the movetext in SAN wrapped at 80 columns
+#[derive(Clone, Debug, Default)]
+pub struct PgnWriter {
+    tags: Vec<(String, String)>,
+    /// Comments by the index of the move in the game history
+    comments: HashMap<usize, String>,
+    /// Clock times left after the move, by the index of the move in the game history
+    clocks: HashMap<usize, Duration>,
+}
+
+impl PgnWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a tag, replacing an earlier value of the same name.
+    /// The Result, SetUp and FEN tags always follow the game and cannot be set.
+    /// A Termination tag replaces the one written for results the final position does not show
+    pub fn tag(mut self, name: &str, value: &str) -> Self {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    /// A comment after the move with the given index in `Game::history`
+    pub fn comment(mut self, ply: usize, text: &str) -> Self {
+        self.comments.insert(ply, text.to_string());
+        self
+    }
+
+    /// The time left on the clock after the move with the given index in `Game::history`,
+    /// written as a [%clk 1:30:00] command in the comment of the move
+    pub fn clock(mut self, ply: usize, left: Duration) -> Self {
+        self.clocks.insert(ply, left);
+        self
+    }
+
+    pub fn write(&self, game: &Game) -> String {
+        let mut start = game.clone();
+        // Only the board counts for finding the start, not how the game ended
+        start.gamestate = start.check_checker();
+        while start.undo_move().is_some() {}
+        let result = result_text(game.outcome());
+
+        let mut tags: Vec<(&str, String)> = SEVEN_TAG_ROSTER
+            .iter()
+            .map(|&name| {
+                let value = match (name, self.tag_value(name)) {
+                    ("Result", _) => result.to_string(),
+                    (_, Some(value)) => value.to_string(),
+                    ("Date", None) => "????.??.??".to_string(),
+                    (_, None) => "?".to_string(),
+                };
+                (name, value)
+            })
+            .collect();
+        let fen = start.to_fen();
+        if fen != Game::new().to_fen() {
+            tags.push(("SetUp", "1".to_string()));
+            tags.push(("FEN", fen));
+        }
+        if let (None, Some(termination)) = (self.tag_value("Termination"), termination(game)) {
+            tags.push(("Termination", termination.to_string()));
+        }
+        for (name, value) in &self.tags {
+            if !tags.iter().any(|(tag, _)| tag == name) && name != "SetUp" && name != "FEN" {
+                tags.push((name, value.clone()));
+            }
+        }
+        let mut pgn = String::new();
+        for (name, value) in tags {
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\"")));
+        }
+        pgn.push('\n');
+
+        let mut words: Vec<String> = Vec::new();
+        let mut needs_number = true;
+        for (ply, chess_move) in game.history().into_iter().enumerate() {
+            match start.side_to_move() {
+                Color::White => words.push(format!("{}.", start.fullmove_number())),
+                Color::Black if needs_number => words.push(format!("{}...", start.fullmove_number())),
+                Color::Black => {}
+            }
+            words.push(start.move_to_san(&chess_move).unwrap_or_else(|_| chess_move.to_string()));
+            start.push_move(chess_move);
+
+            let mut comment = Vec::new();
+            if let Some(left) = self.clocks.get(&ply) {
+                let seconds = left.as_secs();
+                comment.push(format!("[%clk {}:{:02}:{:02}]", seconds / 3600, seconds / 60 % 60, seconds % 60));
+            }
+            if let Some(text) = self.comments.get(&ply) {
+                comment.push(text.replace('}', ""));
+            }
+            needs_number = !comment.is_empty();
+            if needs_number {
+                words.push(format!("{{{}}}", comment.join(" ")));
+            }
+        }
+        words.push(result.to_string());
+
+        let mut line = String::new();
+        for word in words.iter().flat_map(|word| word.split_whitespace()) {
+            if !line.is_empty() && line.len() + 1 + word.len() > LINE_WIDTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        pgn
+    }
+
+    fn tag_value(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// The result token ending the movetext
+fn result_text(outcome: Outcome) -> &'static str {
+    match outcome {
+        Outcome::Ongoing => "*",
+        Outcome::WhiteWins(_) => "1-0",
+        Outcome::BlackWins(_) => "0-1",
+        Outcome::Draw(_) => "1/2-1/2",
+    }
+}
+
+/// The Termination tag for a result the final position does not show,
+/// so that reading the game back ends it the same way
+fn termination(game: &Game) -> Option<&'static str> {
+    if matches!(game.check_checker(), GameState::GameOver(_)) {
+        return None;
+    }
+    match game.outcome() {
+        Outcome::Ongoing | Outcome::WhiteWins(Reason::Unknown) | Outcome::BlackWins(Reason::Unknown) => None,
+        Outcome::Draw(Reason::Unknown) => None,
+        Outcome::WhiteWins(Reason::Timeout) | Outcome::BlackWins(Reason::Timeout) => Some("time forfeit"),
+        Outcome::Draw(Reason::InsufficientMaterial) => Some("time forfeit"),
+        _ => Some("normal"),
+    }
+}
+
+/// Reads the games of a PGN file one after the other, so only
+/// the game being read has to be kept in memory.
+/// A game that cannot be read gives an error and the reader goes on with the next one
+pub struct PgnReader<R> {
+    lines: Lines<R>,
+    /// Number of the last line taken from the input
+    line_number: usize,
+    /// The first line of the next game, read while looking for the end of the last one
+    next_line: Option<(usize, String)>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(input: R) -> Self {
+        Self {
+            lines: input.lines(),
+            line_number: 0,
+            next_line: None,
+        }
+    }
+
+    /// The lines of the next game with their line numbers.
+    /// A game ends with its result, or where a tag starts after its movetext,
+    /// outside of any comment or variation. Whatever follows the result
+    /// on the same line is kept for the next game
+    fn read_game(&mut self) -> Result<Vec<(usize, String)>, ChessError> {
+        let mut lines = Vec::new();
+        let mut in_comment = false;
+        let mut movetext = false;
+        let mut depth = 0usize;
+        loop {
+            let (number, line) = match self.next_line.take() {
+                Some(next) => next,
+                None => match self.lines.next() {
+                    None => return Ok(lines),
+                    Some(line) => {
+                        self.line_number += 1;
+                        let line = line.map_err(|error| invalid(self.line_number, error.to_string()))?;
+                        (self.line_number, line)
+                    }
+                },
+            };
+            if !in_comment && line.starts_with('%') {
+                continue;
+            }
+            if !in_comment && line.trim_start().starts_with('[') {
+                if movetext {
+                    self.next_line = Some((number, line));
+                    return Ok(lines);
+                }
+                lines.push((number, line));
+                continue;
+            }
+
+            let mut word = String::new();
+            let mut end = None;
+            for (index, c) in line.char_indices().chain(Some((line.len(), ' '))) {
+                if in_comment {
+                    in_comment = c != '}';
+                    continue;
+                }
+                if is_symbol_char(c) {
+                    word.push(c);
+                    movetext = true;
+                    continue;
+                }
+                if depth == 0 && is_result(&word) {
+                    end = Some(index);
+                    break;
+                }
+                word.clear();
+                match c {
+                    '{' => in_comment = true,
+                    ';' => break,
+                    '(' => depth += 1,
+                    ')' => depth = depth.saturating_sub(1),
+                    '*' if depth == 0 => {
+                        end = Some(index + 1);
+                        break;
+                    }
+                    _ => {}
+                }
+                if !c.is_whitespace() {
+                    movetext = true;
+                }
+            }
+            match end {
+                Some(end) => {
+                    let rest = &line[end..];
+                    if !rest.trim().is_empty() {
+                        self.next_line = Some((number, rest.to_string()));
+                    }
+                    lines.push((number, line[..end].to_string()));
+                    return Ok(lines);
+                }
+                None => lines.push((number, line)),
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, ChessError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let lines = match self.read_game() {
+            Ok(lines) => lines,
+            Err(error) => return Some(Err(error)),
+        };
+        if lines.iter().all(|(_, line)| line.trim().is_empty()) {
+            return None;
+        }
+        Some(tokenize(&lines).and_then(|tokens| Parser { tokens, position: 0 }.game()))
+    }
+}
+
+fn invalid(line: usize, reason: String) -> ChessError {
+    ChessError::InvalidPgn { line, reason }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    TagStart,
+    TagEnd,
+    Text(String),
+    /// Moves, move numbers, tag names and results
+    Symbol(String),
+    Period,
+    Nag(u8),
+    Comment(String),
+    VariationStart,
+    VariationEnd,
+}
+
+/// Characters that can be part of a move, a move number, a tag name or a result
+fn is_symbol_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_+#=:-/!?".contains(c)
+}
+
+/// Splits the lines of a game into tokens, each with the line it starts on
+fn tokenize(lines: &[(usize, String)]) -> Result<Vec<(usize, Token)>, ChessError> {
+    let mut chars = lines
+        .iter()
+        .flat_map(|(number, line)| line.chars().chain(Some('\n')).map(move |c| (*number, c)))
+        .peekable();
+    let mut tokens = Vec::new();
+    while let Some((line, c)) = chars.next() {
+        let token = match c {
+            _ if c.is_whitespace() => continue,
+            '[' => Token::TagStart,
+            ']' => Token::TagEnd,
+            '(' => Token::VariationStart,
+            ')' => Token::VariationEnd,
+            '.' => Token::Period,
+            '*' => Token::Symbol("*".to_string()),
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '}')) => break,
+                        Some((_, c)) => comment.push(if c == '\n' { ' ' } else { c }),
+                        None => return Err(invalid(line, "the comment is never closed".to_string())),
+                    }
+                }
+                Token::Comment(comment.trim().to_string())
+            }
+            ';' => {
+                let comment: String = chars.by_ref().map(|(_, c)| c).take_while(|&c| c != '\n').collect();
+                Token::Comment(comment.trim().to_string())
+            }
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c)) if c != '\n' => text.push(c),
+                            _ => return Err(invalid(line, "the tag value is never closed".to_string())),
+                        },
+                        Some((_, c)) if c != '\n' => text.push(c),
+                        _ => return Err(invalid(line, "the tag value is never closed".to_string())),
+                    }
+                }
+                Token::Text(text)
+            }
+            '$' => {
+                let mut digits = String::new();
+                while let Some(&(_, c)) = chars.peek().filter(|(_, c)| c.is_ascii_digit()) {
+                    digits.push(c);
+                    chars.next();
+                }
+                let nag = digits
+                    .parse()
+                    .map_err(|_| invalid(line, format!("\"${}\" is not an annotation glyph", digits)))?;
+                Token::Nag(nag)
+            }
+            _ if is_symbol_char(c) => {
+                let mut symbol = c.to_string();
+                while let Some(&(_, c)) = chars.peek().filter(|(_, c)| is_symbol_char(*c)) {
+                    symbol.push(c);
+                    chars.next();
+                }
+                Token::Symbol(symbol)
+            }
+            _ => return Err(invalid(line, format!("unexpected character '{}'", c))),
+        };
+        tokens.push((line, token));
+    }
+    Ok(tokens)
+}
+
+/// The glyph for a move annotation like "!?"
+fn suffix_nag(suffix: &str) -> Option<u8> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+fn is_result(symbol: &str) -> bool {
+    matches!(symbol, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+/// How a game the board does not show as over came to the result,
+/// going by the Termination tag. Without one the reason is unknown
+fn off_board_outcome(game: &Game, result: &str, tags: &[(String, String)]) -> Option<Outcome> {
+    let termination = tags.iter().find(|(name, _)| name == "Termination").map(|(_, value)| value.as_str());
+    let decisive = match termination {
+        Some("normal") => Reason::Resignation,
+        Some("time forfeit") => Reason::Timeout,
+        _ => Reason::Unknown,
+    };
+    let draw = match termination {
+        Some("normal") => game.can_claim_draw().unwrap_or(Reason::Agreement),
+        Some("time forfeit") => Reason::InsufficientMaterial,
+        _ => Reason::Unknown,
+    };
+    match result {
+        "1-0" => Some(Outcome::WhiteWins(decisive)),
+        "0-1" => Some(Outcome::BlackWins(decisive)),
+        "1/2-1/2" => Some(Outcome::Draw(draw)),
+        _ => None,
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<(usize, Token)> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// The line of the last token, for errors at the end of the game
+    fn last_line(&self) -> usize {
+        self.tokens.last().map_or(1, |(line, _)| *line)
+    }
+
+    fn game(mut self) -> Result<PgnGame, ChessError> {
+        let mut tags = Vec::new();
+        let mut fen = None;
+        while let Some((line, Token::TagStart)) = self.tokens.get(self.position).cloned() {
+            self.position += 1;
+            match (self.next(), self.next(), self.next()) {
+                (Some((_, Token::Symbol(name))), Some((_, Token::Text(value))), Some((_, Token::TagEnd))) => {
+                    if name == "FEN" && fen.is_none() {
+                        fen = Some((line, value.clone()));
+                    }
+                    tags.push((name, value))
+                }
+                _ => return Err(invalid(line, "a tag has to look like [Name \"value\"]".to_string())),
+            }
+        }
+
+        let mut game = match fen {
+            Some((line, fen)) => Game::from_fen(&fen).map_err(|error| invalid(line, error.to_string()))?,
+            None => Game::new(),
+        };
+        let (moves, result) = self.line(&mut game, 0)?;
+        let result = result.ok_or_else(|| invalid(self.last_line(), "the game has no result".to_string()))?;
+        if game.outcome().is_over() {
+            if result != result_text(game.outcome()) {
+                return Err(invalid(
+                    self.last_line(),
+                    format!("the result {} does not match the final position", result),
+                ));
+            }
+        } else if let Some(outcome) = off_board_outcome(&game, &result, &tags) {
+            game.end_game(outcome).map_err(|error| invalid(self.last_line(), error.to_string()))?;
+        }
+        Ok(PgnGame { tags, moves, result, game })
+    }
+
+    /// Plays the moves of the main line or of a variation on the game,
+    /// returning them with the result that ends the main line
+    fn line(&mut self, game: &mut Game, depth: usize) -> Result<(Vec<PgnMove>, Option<String>), ChessError> {
+        let mut moves: Vec<PgnMove> = Vec::new();
+        let mut comments_before = Vec::new();
+        loop {
+            let (line, token) = match self.next() {
+                Some(token) => token,
+                None if depth == 0 => return Ok((moves, None)),
+                None => return Err(invalid(self.last_line(), "a variation is never closed".to_string())),
+            };
+            match token {
+                Token::Symbol(symbol) if is_result(&symbol) => {
+                    if depth > 0 {
+                        return Err(invalid(line, format!("result {} inside a variation", symbol)));
+                    }
+                    return Ok((moves, Some(symbol)));
+                }
+                Token::Symbol(symbol) if symbol.chars().all(|c| c.is_ascii_digit()) => {}
+                Token::Period => {}
+                Token::Symbol(symbol) => {
+                    let san = symbol.trim_end_matches(['!', '?']);
+                    let number = match game.side_to_move() {
+                        Color::White => format!("{}.", game.fullmove_number()),
+                        Color::Black => format!("{}...", game.fullmove_number()),
+                    };
+                    let failed = |error: ChessError| invalid(line, format!("{} {}: {}", number, symbol, error));
+                    let chess_move = game.parse_san(san).map_err(failed)?;
+                    game.play(chess_move).map_err(failed)?;
+                    moves.push(PgnMove {
+                        san: san.to_string(),
+                        chess_move,
+                        nags: suffix_nag(&symbol[san.len()..]).into_iter().collect(),
+                        comments_before: std::mem::take(&mut comments_before),
+                        comments: Vec::new(),
+                        variations: Vec::new(),
+                    });
+                }
+                Token::Nag(nag) => match moves.last_mut() {
+                    Some(last) => last.nags.push(nag),
+                    None => return Err(invalid(line, format!("${} comes before any move", nag))),
+                },
+                Token::Comment(comment) => match moves.last_mut() {
+                    Some(last) => last.comments.push(comment),
+                    None => comments_before.push(comment),
+                },
+                Token::VariationStart => {
+                    let last = match moves.last_mut() {
+                        Some(last) => last,
+                        None => return Err(invalid(line, "a variation comes before any move".to_string())),
+                    };
+                    let mut branch = game.clone();
+                    branch.undo_move();
+                    let (variation, _) = self.line(&mut branch, depth + 1)?;
+                    last.variations.push(variation);
+                }
+                Token::VariationEnd if depth > 0 => return Ok((moves, None)),
+                Token::VariationEnd => return Err(invalid(line, "a variation is closed that was never opened".to_string())),
+                Token::TagStart | Token::TagEnd | Token::Text(_) => {
+                    return Err(invalid(line, "tags have to come before the moves".to_string()))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{fen, Color, File, Game, Piece, Rank, Square};
+
+/// ANSI escape codes for the backgrounds and pieces, from the 256 color palette
+const LIGHT_SQUARE: &str = "48;5;180";
+const DARK_SQUARE: &str = "48;5;137";
+const MARKED_SQUARE: &str = "48;5;143";
+const TARGET_SQUARE: &str = "48;5;108";
+const WHITE_PIECE: &str = "38;5;231";
+const BLACK_PIECE: &str = "38;5;16";
+const RESET: &str = "\x1b[0m";
+
+/// How `Game::render` draws the board for a terminal
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderOptions {
+    /// Chess glyphs like ♔ and ♟ instead of FEN letters
+    pub unicode: bool,
+    /// Shades the squares with ANSI colors. Without colors the highlighted
+    /// squares are marked with brackets, [ ] for the last move and the
+    /// selected square and ( ) for the squares the selected piece can go to
+    pub ansi_colors: bool,
+    /// The side shown at the bottom of the board
+    pub orientation: Color,
+    /// Rank numbers and file letters along the edges
+    pub coordinates: bool,
+    /// Highlights the squares the last move came from and went to
+    pub last_move: bool,
+    /// Highlights the square and every square the piece on it can legally move to
+    pub selected: Option<Square>,
+}
+
+impl RenderOptions {
+    /// Letters and dots only, which shows up the same on every terminal
+    pub fn ascii() -> Self {
+        Self {
+            unicode: false,
+            ansi_colors: false,
+            orientation: Color::White,
+            coordinates: true,
+            last_move: true,
+            selected: None,
+        }
+    }
+
+    /// Chess glyphs on colored squares
+    pub fn unicode() -> Self {
+        Self {
+            unicode: true,
+            ansi_colors: true,
+            ..Self::ascii()
+        }
+    }
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self::ascii()
+    }
+}
+
+/// How a square stands out from the others
+#[derive(Copy, Clone, PartialEq)]
+enum Highlight {
+    None,
+    /// The squares of the last move and the selected square
+    Marked,
+    /// A square the selected piece can move to
+    Target,
+}
+
+impl Game {
+    /// Draws the board for a terminal, three columns to a square
+    pub fn render(&self, options: &RenderOptions) -> String {
+        let mut ranks = Rank::ALL;
+        let mut files = File::ALL;
+        match options.orientation {
+            Color::White => ranks.reverse(),
+            Color::Black => files.reverse(),
+        }
+        let mut marked: Vec<(Square, Highlight)> = Vec::new();
+        if let Some(last) = self.history.last().filter(|_| options.last_move) {
+            marked.push((last.chess_move.from, Highlight::Marked));
+            marked.push((last.chess_move.to, Highlight::Marked));
+        }
+        if let Some(selected) = options.selected {
+            marked.push((selected, Highlight::Marked));
+            for chess_move in self.moves_from(selected) {
+                marked.push((chess_move.to, Highlight::Target));
+            }
+        }
+
+        let mut output = String::new();
+        for rank in ranks {
+            if options.coordinates {
+                output.push(rank.to_char());
+                output.push(' ');
+            }
+            for file in files {
+                let square = Square::new(file, rank);
+                let highlight = marked
+                    .iter()
+                    .rev()
+                    .find(|(marked, _)| *marked == square)
+                    .map_or(Highlight::None, |(_, highlight)| *highlight);
+                output.push_str(&self.render_square(square, highlight, options));
+            }
+            output.push('\n');
+        }
+        if options.coordinates {
+            output.push_str("  ");
+            for file in files {
+                output.push(' ');
+                output.push(file.to_char());
+                output.push(' ');
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    fn render_square(&self, square: Square, highlight: Highlight, options: &RenderOptions) -> String {
+        let piece = self.piece_at(square);
+        let glyph = match piece {
+            Some((piece, color)) if options.unicode => glyph(piece, color),
+            Some((piece, color)) => fen::piece_char(piece, color),
+            None if options.ansi_colors => ' ',
+            None => '.',
+        };
+        if !options.ansi_colors {
+            return match highlight {
+                Highlight::None => format!(" {} ", glyph),
+                Highlight::Marked => format!("[{}]", glyph),
+                Highlight::Target => format!("({})", glyph),
+            };
+        }
+        let background = match highlight {
+            Highlight::Marked => MARKED_SQUARE,
+            Highlight::Target => TARGET_SQUARE,
+            Highlight::None if square.is_dark() => DARK_SQUARE,
+            Highlight::None => LIGHT_SQUARE,
+        };
+        let foreground = match piece {
+            Some((_, Color::Black)) => BLACK_PIECE,
+            _ => WHITE_PIECE,
+        };
+        format!("\x1b[{};{}m {} {}", background, foreground, glyph, RESET)
+    }
+}
+
+/// The Unicode chess symbol of the piece, outlined for White and filled for Black
+fn glyph(piece: Piece, color: Color) -> char {
+    use Piece::*;
+    match (piece, color) {
+        (King, Color::White) => '♔',
+        (Queen, Color::White) => '♕',
+        (Rook, Color::White) => '♖',
+        (Bishop, Color::White) => '♗',
+        (Knight, Color::White) => '♘',
+        (Pawn, Color::White) => '♙',
+        (King, Color::Black) => '♚',
+        (Queen, Color::Black) => '♛',
+        (Rook, Color::Black) => '♜',
+        (Bishop, Color::Black) => '♝',
+        (Knight, Color::Black) => '♞',
+        (Pawn, Color::Black) => '♟',
+    }
+}
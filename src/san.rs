@@ -0,0 +1,151 @@
+use crate::{ChessError, File, Game, Move, Piece, Rank, Square};
+
+/// Reading and writing moves in Standard Algebraic Notation, the way players write them:
+/// the piece letter, the destination square and only as much of the
+/// starting square as is needed to tell the moves apart, like "Nbd7", "exd5", "e8=Q" or "O-O"
+impl Game {
+    /// Finds the legal move the SAN text stands for.
+    /// Check and mate marks as well as annotations like "!?" at the end are skipped
+    pub fn parse_san(&self, san: &str) -> Result<Move, ChessError> {
+        let invalid = || ChessError::InvalidSan(san.to_string());
+        let text = san.trim_end_matches(['+', '#', '!', '?']);
+        let legal = self.legal_moves();
+
+        let matches: Vec<Move> = if let Some(file) = castling_file(text) {
+            legal
+                .into_iter()
+                .filter(|chess_move| chess_move.flags.castling && chess_move.to.file() == file)
+                .collect()
+        } else {
+            let mut chars: Vec<char> = text.chars().collect();
+            let piece = match chars.first() {
+                Some(&c) if c.is_ascii_uppercase() => {
+                    chars.remove(0);
+                    Piece::from_char(c).filter(|&piece| piece != Piece::Pawn).ok_or_else(invalid)?
+                }
+                _ => Piece::Pawn,
+            };
+            let promotion = match chars.last() {
+                Some(&c) if c.is_ascii_uppercase() => {
+                    chars.pop();
+                    if chars.last() == Some(&'=') {
+                        chars.pop();
+                    }
+                    let promotion = Piece::from_char(c)
+                        .filter(|promotion| !matches!(promotion, Piece::Pawn | Piece::King))
+                        .ok_or_else(invalid)?;
+                    Some(promotion)
+                }
+                _ => None,
+            };
+            if chars.len() < 2 {
+                return Err(invalid());
+            }
+            let to: Square = chars
+                .split_off(chars.len() - 2)
+                .into_iter()
+                .collect::<String>()
+                .parse()
+                .map_err(|_| invalid())?;
+            let capture = chars.last() == Some(&'x');
+            if capture {
+                chars.pop();
+            }
+            let (mut file, mut rank) = (None, None);
+            for c in chars {
+                match (File::from_char(c), Rank::from_char(c)) {
+                    (Some(from_file), _) if file.is_none() && rank.is_none() => file = Some(from_file),
+                    (_, Some(from_rank)) if rank.is_none() => rank = Some(from_rank),
+                    _ => return Err(invalid()),
+                }
+            }
+            if piece == Piece::Pawn && capture && file.is_none() {
+                return Err(invalid());
+            }
+
+            legal
+                .into_iter()
+                .filter(|chess_move| {
+                    matches!(self.piece_at(chess_move.from), Some((moving, _)) if moving == piece)
+                        && !chess_move.flags.castling
+                        && chess_move.to == to
+                        && chess_move.promotion == promotion
+                        && file.map_or(true, |file| chess_move.from.file() == file)
+                        && rank.map_or(true, |rank| chess_move.from.rank() == rank)
+                        && (!capture || chess_move.flags.capture)
+                })
+                .collect()
+        };
+
+        match matches[..] {
+            [chess_move] => Ok(chess_move),
+            [] => Err(ChessError::IllegalSan(san.to_string())),
+            _ => Err(ChessError::AmbiguousSan(san.to_string())),
+        }
+    }
+
+    /// Writes a legal move in SAN, with "+" after a check and "#" after a mate
+    pub fn move_to_san(&self, chess_move: &Move) -> Result<String, ChessError> {
+        let legal = self.legal_moves();
+        let chess_move = *legal
+            .iter()
+            .find(|legal| legal.same_as(chess_move))
+            .ok_or(ChessError::IllegalMove(*chess_move))?;
+        let Move { from, to, promotion, flags } = chess_move;
+        let piece = match self.piece_at(from) {
+            Some((piece, _)) => piece,
+            None => return Err(ChessError::EmptySquare(from)),
+        };
+
+        let mut san = String::new();
+        if flags.castling {
+            san.push_str(if to.file() == File::G { "O-O" } else { "O-O-O" });
+        } else if piece == Piece::Pawn {
+            if flags.capture {
+                san.push(from.file().to_char());
+                san.push('x');
+            }
+            san.push_str(&to.to_string());
+            if let Some(promotion) = promotion {
+                san.push('=');
+                san.push(promotion.to_char().to_ascii_uppercase());
+            }
+        } else {
+            san.push(piece.to_char().to_ascii_uppercase());
+            let rivals: Vec<Square> = legal
+                .iter()
+                .filter(|other| other.to == to && other.from != from && self.piece_at(other.from) == self.piece_at(from))
+                .map(|other| other.from)
+                .collect();
+            if !rivals.is_empty() {
+                if rivals.iter().all(|rival| rival.file() != from.file()) {
+                    san.push(from.file().to_char());
+                } else if rivals.iter().all(|rival| rival.rank() != from.rank()) {
+                    san.push(from.rank().to_char());
+                } else {
+                    san.push_str(&from.to_string());
+                }
+            }
+            if flags.capture {
+                san.push('x');
+            }
+            san.push_str(&to.to_string());
+        }
+
+        let mut after = self.position_only();
+        after.apply_move(chess_move);
+        if after.in_check(after.color) {
+            san.push(if after.legal_moves().is_empty() { '#' } else { '+' });
+        }
+        Ok(san)
+    }
+}
+
+/// The file the king lands on for "O-O" and "O-O-O", also written with zeros
+fn castling_file(text: &str) -> Option<File> {
+    match text {
+        "O-O" | "0-0" => Some(File::G),
+        "O-O-O" | "0-0-0" => Some(File::C),
+        _ => None,
+    }
+}
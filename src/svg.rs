@@ -0,0 +1,214 @@
+use crate::{Color, Game, Piece, Square};
+
+/// Side of a square in the units of the drawing
+const SQUARE: f64 = 45.0;
+const LIGHT_SQUARE: &str = "#f0d9b5";
+const DARK_SQUARE: &str = "#b58863";
+const HIGHLIGHT: &str = "#9bc700";
+const CHECK: &str = "#e0301e";
+const ARROW: &str = "#15781b";
+
+/// What `Game::to_svg` draws besides the pieces
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgOptions {
+    /// Width and height of the image in pixels, the drawing scales to fit
+    pub size: u32,
+    /// The side shown at the bottom of the board
+    pub orientation: Color,
+    /// Rank numbers and file letters in the corners of the edge squares
+    pub coordinates: bool,
+    /// Highlights the squares the last move came from and went to
+    pub last_move: bool,
+    /// Marks the king of the side to move in red when it is in check
+    pub check: bool,
+    /// More squares to highlight
+    pub highlights: Vec<Square>,
+    /// Arrows from one square to another, drawn on top of the pieces
+    pub arrows: Vec<(Square, Square)>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            size: 360,
+            orientation: Color::White,
+            coordinates: true,
+            last_move: true,
+            check: true,
+            highlights: Vec::new(),
+            arrows: Vec::new(),
+        }
+    }
+}
+
+impl Game {
+    /// The board as a self-contained SVG image. The pieces are drawn
+    /// as shapes inside the image, so it does not load any other files
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        let board = 8.0 * SQUARE;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {board} {board}\" width=\"{size}\" height=\"{size}\">\n",
+            board = board,
+            size = options.size
+        );
+
+        for square in Square::all() {
+            let (x, y) = corner(square, options.orientation);
+            let fill = if square.is_dark() { DARK_SQUARE } else { LIGHT_SQUARE };
+            svg.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n", x, y, SQUARE, SQUARE, fill));
+        }
+
+        let mut highlights = options.highlights.clone();
+        if let Some(last) = self.history.last().filter(|_| options.last_move) {
+            highlights.extend([last.chess_move.from, last.chess_move.to]);
+        }
+        for square in highlights {
+            let (x, y) = corner(square, options.orientation);
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" fill-opacity=\"0.4\"/>\n",
+                x, y, SQUARE, SQUARE, HIGHLIGHT
+            ));
+        }
+        if options.check && self.in_check(self.color) {
+            if let Some(king) = self.king_position(self.color) {
+                let (x, y) = corner(king, options.orientation);
+                svg.push_str(&format!(
+                    "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" fill-opacity=\"0.7\"/>\n",
+                    x + SQUARE / 2.0,
+                    y + SQUARE / 2.0,
+                    SQUARE / 2.0,
+                    CHECK
+                ));
+            }
+        }
+
+        if options.coordinates {
+            for square in Square::all() {
+                let (x, y) = corner(square, options.orientation);
+                let color = if square.is_dark() { LIGHT_SQUARE } else { DARK_SQUARE };
+                if y == 7.0 * SQUARE {
+                    svg.push_str(&format!(
+                        "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"9\" text-anchor=\"end\" fill=\"{}\">{}</text>\n",
+                        x + SQUARE - 2.0,
+                        y + SQUARE - 2.0,
+                        color,
+                        square.file()
+                    ));
+                }
+                if x == 0.0 {
+                    svg.push_str(&format!(
+                        "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"9\" fill=\"{}\">{}</text>\n",
+                        x + 2.0,
+                        y + 10.0,
+                        color,
+                        square.rank()
+                    ));
+                }
+            }
+        }
+
+        for square in Square::all() {
+            if let Some((piece, color)) = self.piece_at(square) {
+                let (x, y) = corner(square, options.orientation);
+                svg.push_str(&format!("<g transform=\"translate({},{})\">{}</g>\n", x, y, artwork(piece, color)));
+            }
+        }
+
+        for &(from, to) in &options.arrows {
+            svg.push_str(&arrow(from, to, options.orientation));
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// The top left corner of the square in the drawing
+fn corner(square: Square, orientation: Color) -> (f64, f64) {
+    let (file, rank) = (square.file().index() as f64, square.rank().index() as f64);
+    match orientation {
+        Color::White => (file * SQUARE, (7.0 - rank) * SQUARE),
+        Color::Black => ((7.0 - file) * SQUARE, rank * SQUARE),
+    }
+}
+
+/// A line from the middle of one square to the middle of the other, ending in a head
+fn arrow(from: Square, to: Square, orientation: Color) -> String {
+    let center = |square| {
+        let (x, y) = corner(square, orientation);
+        (x + SQUARE / 2.0, y + SQUARE / 2.0)
+    };
+    let ((x1, y1), (x2, y2)) = (center(from), center(to));
+    let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt().max(1.0);
+    let (dx, dy) = ((x2 - x1) / length, (y2 - y1) / length);
+    let head = 18.0;
+    let half = 11.0;
+    let (bx, by) = (x2 - dx * head, y2 - dy * head);
+    format!(
+        "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{color}\" stroke-width=\"9\" stroke-linecap=\"round\" opacity=\"0.8\"/>\n\
+         <polygon points=\"{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}\" fill=\"{color}\" opacity=\"0.8\"/>\n",
+        x1,
+        y1,
+        bx,
+        by,
+        x2,
+        y2,
+        bx - dy * half,
+        by + dx * half,
+        bx + dy * half,
+        by - dx * half,
+        color = ARROW
+    )
+}
+
+/// The drawing of a piece within a square of 45 by 45.
+/// White pieces are white with a black outline and Black pieces
+/// are black, with the inner lines in the other color
+fn artwork(piece: Piece, color: Color) -> String {
+    let (fill, detail) = match color {
+        Color::White => ("#fff", "#000"),
+        Color::Black => ("#000", "#fff"),
+    };
+    let base = "<rect x=\"11\" y=\"35\" width=\"23\" height=\"4\" rx=\"1\"/>";
+    let shapes = match piece {
+        Piece::Pawn => "<path d=\"M16 36 H29 C29 30 26.5 26 25 23 H20 C18.5 26 16 30 16 36 Z\"/>\
+                        <circle cx=\"22.5\" cy=\"17\" r=\"5.5\"/>\
+                        <rect x=\"13\" y=\"35\" width=\"19\" height=\"4\" rx=\"1\"/>"
+            .to_string(),
+        Piece::Rook => format!(
+            "{}<rect x=\"14\" y=\"16\" width=\"17\" height=\"19\"/>\
+             <path d=\"M12 9 H16 V12 H20 V9 H25 V12 H29 V9 H33 V16 H12 Z\"/>\
+             <path d=\"M14 30 H31 M14 20 H31\" stroke=\"{}\" fill=\"none\"/>",
+            base, detail
+        ),
+        Piece::Knight => format!(
+            "{}<path d=\"M13 36 H32 C32 27 31 18 24 12 L22 7 L20 11 C15 13 10 19 11 25 L14 27 L19 24 C17 29 13 31 13 36 Z\"/>\
+             <circle cx=\"18\" cy=\"17\" r=\"1.5\" fill=\"{}\" stroke=\"none\"/>",
+            base, detail
+        ),
+        Piece::Bishop => format!(
+            "{}<path d=\"M15 35 C15 27 18 19 22.5 13 C27 19 30 27 30 35 Z\"/>\
+             <circle cx=\"22.5\" cy=\"10\" r=\"2.5\"/>\
+             <path d=\"M22.5 20 V28 M18.5 24 H26.5\" stroke=\"{}\" fill=\"none\"/>",
+            base, detail
+        ),
+        Piece::Queen => format!(
+            "{}<path d=\"M12 35 L10 14 L16 25 L16.5 10 L20.5 24 L22.5 8 L24.5 24 L28.5 10 L29 25 L35 14 L33 35 Z\"/>\
+             <circle cx=\"10\" cy=\"14\" r=\"2\"/><circle cx=\"16.5\" cy=\"10\" r=\"2\"/>\
+             <circle cx=\"22.5\" cy=\"8\" r=\"2\"/><circle cx=\"28.5\" cy=\"10\" r=\"2\"/>\
+             <circle cx=\"35\" cy=\"14\" r=\"2\"/>\
+             <path d=\"M13 31 H32\" stroke=\"{}\" fill=\"none\"/>",
+            base, detail
+        ),
+        Piece::King => format!(
+            "{}<path d=\"M22.5 5 V13 M19 8.5 H26\" fill=\"none\"/>\
+             <path d=\"M12 35 C7 27 11 18 18 21 C19 15 26 15 27 21 C34 18 38 27 33 35 Z\"/>\
+             <path d=\"M13 31 H32 M22.5 21 V31\" stroke=\"{}\" fill=\"none\"/>",
+            base, detail
+        ),
+    };
+    format!(
+        "<g fill=\"{}\" stroke=\"#000\" stroke-width=\"1.5\" stroke-linejoin=\"round\">{}</g>",
+        fill, shapes
+    )
+}
@@ -0,0 +1,26 @@
+use crate::{ChessError, Game, GameState, Move};
+
+/// Moves in the long algebraic notation of the Universal Chess Interface,
+/// the starting square, the destination square and a lowercase promotion piece,
+/// like "e2e4" or "e7e8q". Castling is written as the king move, "e1g1" or "e8c8"
+impl Game {
+    /// Finds the legal move for the UCI text. Anything that is not exactly
+    /// four or five lowercase characters naming a legal move is refused
+    pub fn parse_uci(&self, text: &str) -> Result<Move, ChessError> {
+        if text.chars().any(|c| !c.is_ascii_lowercase() && !c.is_ascii_digit()) {
+            return Err(ChessError::InvalidMove(text.to_string()));
+        }
+        self.find_legal(text.parse()?)
+    }
+
+    /// Writes a legal move in UCI notation
+    pub fn move_to_uci(&self, chess_move: &Move) -> Result<String, ChessError> {
+        self.find_legal(*chess_move).map(|legal| legal.to_string())
+    }
+
+    /// `play` for a move in UCI notation
+    pub fn play_uci(&mut self, text: &str) -> Result<GameState, ChessError> {
+        let chess_move = self.parse_uci(text)?;
+        self.play(chess_move)
+    }
+}